@@ -0,0 +1,82 @@
+//! Checks X's current pages against a known-good fingerprint.
+//!
+//! ```sh
+//! cargo run --example canary -- <snapshot.txt> [--update] [<home.html> <ondemand.js>]
+//! ```
+//!
+//! Fetches the homepage and ondemand.js (or reads saved copies), runs every
+//! extraction stage and compares the structure found with the snapshot.
+//! Prints what drifted and exits with 1 if anything did. With `--update`, or
+//! if the snapshot doesn't exist yet, writes the current fingerprint to it
+//! instead.
+
+use std::{
+   env,
+   error::Error,
+   fs,
+   path::Path,
+   process,
+};
+
+use xitter_txid::{
+   FetchOptions,
+   canary::{
+      self,
+      Fingerprint,
+   },
+};
+
+fn main() {
+   let mut args = env::args().skip(1).collect::<Vec<_>>();
+   let update = args.iter().any(|arg| arg == "--update");
+   args.retain(|arg| arg != "--update");
+
+   let (snapshot, pages) = match *args.as_slice() {
+      [ref snapshot] => (snapshot, None),
+      [ref snapshot, ref html, ref js] => (snapshot, Some((html, js))),
+      _ => {
+         eprintln!("usage: canary <snapshot.txt> [--update] [<home.html> <ondemand.js>]");
+         process::exit(2);
+      },
+   };
+
+   match check(Path::new(snapshot), pages, update) {
+      Ok(true) => {},
+      Ok(false) => process::exit(1),
+      Err(err) => {
+         eprintln!("error: {err}");
+         process::exit(1);
+      },
+   }
+}
+
+/// Returns whether the pages still match the snapshot.
+fn check(
+   snapshot: &Path,
+   pages: Option<(&String, &String)>,
+   update: bool,
+) -> Result<bool, Box<dyn Error>> {
+   let current = match pages {
+      Some((html, js)) => Fingerprint::new(&fs::read_to_string(html)?, &fs::read_to_string(js)?),
+      None => canary::fetch(&FetchOptions::default())?,
+   };
+
+   if update || !snapshot.exists() {
+      fs::write(snapshot, current.to_string())?;
+      println!("wrote {}", snapshot.display());
+      return Ok(true);
+   }
+
+   let known_good = fs::read_to_string(snapshot)?.parse::<Fingerprint>()?;
+   let drift = current.diff(&known_good);
+   if drift.is_empty() {
+      println!("no drift from {}", snapshot.display());
+      return Ok(true);
+   }
+
+   eprintln!("X's pages drifted from {}:", snapshot.display());
+   for drift in drift {
+      eprintln!("  {drift}");
+   }
+   Ok(false)
+}
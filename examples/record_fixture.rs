@@ -0,0 +1,90 @@
+//! Records a golden fixture from a saved homepage and ondemand.js.
+//!
+//! ```sh
+//! cargo run --example record_fixture -- <name> <home.html> <ondemand.js>
+//! ```
+//!
+//! Copies both files into `tests/fixtures/<name>/` and writes the values the
+//! current implementation derives from them to `expected.txt`.
+
+use std::{
+   env,
+   error::Error,
+   fmt::Write as _,
+   fs,
+   path::Path,
+   process,
+};
+
+use data_encoding::BASE64;
+use xitter_txid::ClientTransaction;
+
+/// Timestamps (seconds since X's epoch) that IDs are recorded at.
+const TIMESTAMPS: [u32; 3] = [0, 1_000_000, 86_400_000];
+
+/// Requests that IDs are recorded for.
+const REQUESTS: [(&str, &str); 2] = [
+   ("GET", "/i/api/graphql/abc123/UserByScreenName"),
+   ("POST", "/i/api/1.1/jot/client_event.json"),
+];
+
+fn main() {
+   let args = env::args().skip(1).collect::<Vec<_>>();
+   let &[ref name, ref html_path, ref js_path] = args.as_slice() else {
+      eprintln!("usage: record_fixture <name> <home.html> <ondemand.js>");
+      process::exit(2);
+   };
+
+   if let Err(err) = record(name, Path::new(html_path), Path::new(js_path)) {
+      eprintln!("error: {err}");
+      process::exit(1);
+   }
+}
+
+fn record(name: &str, html_path: &Path, js_path: &Path) -> Result<(), Box<dyn Error>> {
+   let html = fs::read_to_string(html_path)?;
+   let js = fs::read_to_string(js_path)?;
+   let client = ClientTransaction::new(&html, &js)?;
+
+   let mut expected = String::new();
+   writeln!(
+      expected,
+      "# Recorded by `cargo run --example record_fixture`."
+   )?;
+   writeln!(
+      expected,
+      "ondemand_url = {}",
+      ClientTransaction::extract_ondemand_url(&html)?
+   )?;
+   writeln!(
+      expected,
+      "verification_key = {}",
+      BASE64.encode(client.key_bytes())
+   )?;
+   writeln!(expected, "row_index = {}", client.row_index())?;
+   let indices = client
+      .key_byte_indices()
+      .iter()
+      .map(ToString::to_string)
+      .collect::<Vec<_>>();
+   writeln!(expected, "key_byte_indices = {}", indices.join(" "))?;
+   writeln!(expected, "animation_key = {}", client.animation_key())?;
+
+   for time in TIMESTAMPS {
+      for (method, path) in REQUESTS {
+         let id = client.generate_transaction_id_at(method, path, time);
+         writeln!(expected, "transaction_id = {time} {method} {path} {id}")?;
+      }
+   }
+
+   let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+      .join("tests/fixtures")
+      .join(name);
+   fs::create_dir_all(&dir)?;
+   fs::write(dir.join("home.html"), html)?;
+   fs::write(dir.join("ondemand.js"), js)?;
+   fs::write(dir.join("expected.txt"), expected)?;
+
+   println!("recorded {}", dir.display());
+   Ok(())
+}
@@ -0,0 +1,82 @@
+//! Renders the loading animation frame an animation key is derived from.
+//!
+//! ```sh
+//! cargo run --example render_animation -- <home.html> <ondemand.js> <out-dir> [steps]
+//! ```
+//!
+//! Writes `selected.svg` at the computed target time and, if `steps` is given,
+//! `step-NNN.svg` for evenly spaced times across the whole animation.
+
+use std::{
+   env,
+   error::Error,
+   fs,
+   path::Path,
+   process,
+};
+
+use xitter_txid::{
+   ClientTransaction,
+   animation::{
+      render_svg,
+      render_svg_sequence,
+   },
+   parse,
+};
+
+fn main() {
+   let args = env::args().skip(1).collect::<Vec<_>>();
+   if !(3..=4).contains(&args.len()) {
+      eprintln!("usage: render_animation <home.html> <ondemand.js> <out-dir> [steps]");
+      process::exit(2);
+   }
+
+   if let Err(err) = render(
+      Path::new(&args[0]),
+      Path::new(&args[1]),
+      Path::new(&args[2]),
+      args.get(3).map(String::as_str),
+   ) {
+      eprintln!("error: {err}");
+      process::exit(1);
+   }
+}
+
+fn render(
+   html_path: &Path,
+   js_path: &Path,
+   out_dir: &Path,
+   steps: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+   let html = fs::read_to_string(html_path)?;
+   let js = fs::read_to_string(js_path)?;
+
+   let selection = ClientTransaction::select_frame(&html, &js)?;
+   let keyframe = selection.keyframe()?;
+   let path = parse::animation_paths(&html)
+      .into_iter()
+      .nth(selection.frame_index)
+      .ok_or("selected frame has no path")?;
+   println!(
+      "frame {} row {} at t={:.4}: {:?}",
+      selection.frame_index, selection.row_index, selection.target_time, selection.row
+   );
+
+   fs::create_dir_all(out_dir)?;
+   fs::write(
+      out_dir.join("selected.svg"),
+      render_svg(&path, &keyframe, selection.target_time),
+   )?;
+
+   if let Some(steps) = steps {
+      for (step, svg) in render_svg_sequence(&path, &keyframe, steps.parse()?)
+         .into_iter()
+         .enumerate()
+      {
+         fs::write(out_dir.join(format!("step-{step:03}.svg")), svg)?;
+      }
+   }
+
+   println!("wrote {}", out_dir.display());
+   Ok(())
+}
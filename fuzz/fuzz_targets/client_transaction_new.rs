@@ -0,0 +1,17 @@
+//! Feeds arbitrary homepage and ondemand.js pairs to `ClientTransaction::new`.
+//!
+//! The input is split at the first NUL byte: HTML before it, JS after it.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use xitter_txid::ClientTransaction;
+
+fuzz_target!(|data: &str| {
+   let (html, js) = data.split_once('\0').unwrap_or((data, data));
+
+   let _ = ClientTransaction::extract_ondemand_url(html);
+   if let Ok(client) = ClientTransaction::new(html, js) {
+      let _ = client.generate_transaction_id_at("GET", "/i/api/1.1/foo.json", 0);
+   }
+});
@@ -0,0 +1,10 @@
+//! Feeds arbitrary strings to the transaction ID decoder.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use xitter_txid::DecodedTransactionId;
+
+fuzz_target!(|data: &str| {
+   let _ = DecodedTransactionId::decode(data);
+});
@@ -0,0 +1,651 @@
+//! Versions of the transaction ID algorithm.
+//!
+//! The version X's web client implements is the last byte of every ID it
+//! generates. Each version is a [`TransactionAlgorithm`]; a [`Registry`]
+//! holds the known ones and picks the one a given ondemand.js implements, so
+//! old and new versions can be served side by side while X rolls one out.
+//!
+//! ```ignore
+//! let mut registry = Registry::default();
+//! registry.register(Arc::new(MyV4));
+//! let client = ClientTransaction::new_with_registry(&html, &js, &registry)?;
+//! ```
+
+use std::{
+   fmt,
+   sync::{
+      Arc,
+      atomic::{
+         AtomicU64,
+         Ordering,
+      },
+   },
+};
+
+use hmac_sha256::Hash;
+
+use crate::{
+   error::Error,
+   ondemand::OndemandIndices,
+   transaction::{
+      ClientTransaction,
+      HASH_BYTES,
+      HASH_SALT,
+      PROTOCOL_VERSION,
+   },
+   utils::{
+      base64_decode_unpadded,
+      base64_encode,
+      random_u64,
+   },
+};
+
+/// One version of the transaction ID algorithm.
+#[expect(
+   clippy::module_name_repetitions,
+   reason = "`Algorithm` alone is ambiguous in callers' imports"
+)]
+pub trait TransactionAlgorithm: Send + Sync {
+   /// Version byte the generated IDs end with.
+   fn version(&self) -> u8;
+
+   /// Whether `ondemand_js` implements this version.
+   ///
+   /// [`Registry::detect`] asks the most recently registered version first,
+   /// and [`V3`] claims any ondemand.js its indices parse from. A later
+   /// version whose ondemand.js still has `(e[N], 16)` calls must check for
+   /// something more specific and be registered after V3.
+   fn detect(&self, ondemand_js: &str) -> bool;
+
+   /// Reads the indices the animation key is derived with. Defaults to V3's
+   /// `(e[N], 16)` calls; a version with another layout parses its own into
+   /// a row index and key byte indices.
+   fn indices(&self, ondemand_js: &str) -> Result<OndemandIndices, Error> {
+      OndemandIndices::parse(ondemand_js)
+   }
+
+   /// Derives the animation key from the verification key, the ondemand.js
+   /// indices and the coordinate table of every `loading-x-anim-N` frame.
+   fn animation_key(
+      &self,
+      key_bytes: &[u8],
+      indices: &OndemandIndices,
+      frames: &[Vec<Vec<i32>>],
+   ) -> Result<String, Error>;
+
+   /// Encodes the ID of a request at `time`, in seconds since X's epoch.
+   fn generate(
+      &self,
+      key_bytes: &[u8],
+      animation_key: &str,
+      method: &str,
+      path: &str,
+      time: u32,
+   ) -> String;
+
+   /// Like [`generate`](Self::generate), masking the ID with `random_byte`
+   /// as a [`RandomSource`] other than the default asks.
+   ///
+   /// Defaults to re-masking the output of `generate`, which assumes V3's
+   /// layout: the mask byte first, then every other byte XOR-ed with it. IDs
+   /// that aren't base64 are returned unchanged.
+   fn generate_with_random_byte(
+      &self,
+      key_bytes: &[u8],
+      animation_key: &str,
+      method: &str,
+      path: &str,
+      time: u32,
+      random_byte: u8,
+   ) -> String {
+      let id = self.generate(key_bytes, animation_key, method, path, time);
+      let Ok(mut bytes) = base64_decode_unpadded(&id) else {
+         return id;
+      };
+      let Some((mask, rest)) = bytes.split_first_mut() else {
+         return id;
+      };
+
+      for byte in rest {
+         *byte ^= *mask ^ random_byte;
+      }
+      *mask = random_byte;
+      base64_encode(&bytes).trim_end_matches('=').to_owned()
+   }
+}
+
+/// Where the byte an ID is masked with comes from.
+///
+/// The web client has used both a byte of the request hash and
+/// `Math.random()`; X accepts either.
+#[derive(Debug, Clone, Default)]
+pub enum RandomSource {
+   /// A byte of the request hash, so the same request at the same time
+   /// always gets the same ID.
+   #[default]
+   HashDerived,
+   /// A fresh byte per ID from the operating system's random number
+   /// generator, like `Math.random()`. Falls back to
+   /// [`ProcessRandom`](Self::ProcessRandom) if the OS source fails.
+   #[cfg(feature = "getrandom")]
+   Os,
+   /// A fresh byte per ID, like `Math.random()`: a hash of the current time
+   /// under the standard library's per-process `RandomState` keys. Not read
+   /// from the OS for every ID, and not cryptographically secure.
+   ProcessRandom,
+   /// A reproducible sequence, for matching the browser's distribution in
+   /// tests. See [`seeded`](Self::seeded).
+   Seeded(SeededRng),
+}
+
+impl RandomSource {
+   /// A [`Seeded`](Self::Seeded) source starting from `seed`.
+   #[must_use]
+   pub const fn seeded(seed: u64) -> Self {
+      Self::Seeded(SeededRng::new(seed))
+   }
+
+   /// The byte to mask the next ID with, or `None` to leave it to the
+   /// algorithm.
+   #[must_use]
+   pub fn next_byte(&self) -> Option<u8> {
+      match *self {
+         Self::HashDerived => None,
+         #[cfg(feature = "getrandom")]
+         Self::Os => {
+            let mut byte = [0];
+            Some(
+               getrandom::getrandom(&mut byte)
+                  .map_or_else(|_| random_u64().to_be_bytes()[0], |()| byte[0]),
+            )
+         },
+         Self::ProcessRandom => Some(random_u64().to_be_bytes()[0]),
+         Self::Seeded(ref rng) => Some(rng.next_u64().to_be_bytes()[0]),
+      }
+   }
+}
+
+/// A `SplitMix64` generator that can be shared between threads.
+///
+/// Clones continue independently from the clone's current state.
+#[derive(Debug)]
+pub struct SeededRng {
+   state: AtomicU64,
+}
+
+impl SeededRng {
+   const GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+   #[must_use]
+   pub const fn new(seed: u64) -> Self {
+      Self {
+         state: AtomicU64::new(seed),
+      }
+   }
+
+   /// The next value of the sequence.
+   pub fn next_u64(&self) -> u64 {
+      let mut value = self
+         .state
+         .fetch_add(Self::GAMMA, Ordering::Relaxed)
+         .wrapping_add(Self::GAMMA);
+      value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+      value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+      value ^ (value >> 31)
+   }
+}
+
+impl Clone for SeededRng {
+   fn clone(&self) -> Self {
+      Self::new(self.state.load(Ordering::Relaxed))
+   }
+}
+
+/// Version 3, served since 2023.
+///
+/// The animation key comes from the loading animation frame the key bytes
+/// select; the ID is the key bytes, the time, a SHA-256 of the request and
+/// the version, each byte masked by XOR with a byte of the same hash.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct V3;
+
+impl V3 {
+   /// Encodes an ID masked with `random_byte`, or else with a byte of the
+   /// request hash.
+   fn encode(
+      key_bytes: &[u8],
+      animation_key: &str,
+      method: &str,
+      path: &str,
+      time: u32,
+      random_byte: Option<u8>,
+   ) -> String {
+      let hash_input = format!("{method}!{path}!{time}{HASH_SALT}{animation_key}");
+      let hash_result = Hash::hash(hash_input.as_bytes());
+
+      let time_bytes = time.to_le_bytes();
+      let random_byte = random_byte.unwrap_or(hash_result[16]);
+
+      let mut bytes = Vec::with_capacity(key_bytes.len() + 4 + HASH_BYTES + 1);
+      bytes.extend_from_slice(key_bytes);
+      bytes.extend_from_slice(&time_bytes);
+      bytes.extend_from_slice(&hash_result[..HASH_BYTES]);
+      bytes.push(PROTOCOL_VERSION);
+
+      let mut encoded = vec![random_byte];
+      encoded.extend(bytes.iter().map(|&byte| byte ^ random_byte));
+
+      base64_encode(&encoded).trim_end_matches('=').to_owned()
+   }
+}
+
+impl TransactionAlgorithm for V3 {
+   fn version(&self) -> u8 {
+      PROTOCOL_VERSION
+   }
+
+   fn detect(&self, ondemand_js: &str) -> bool {
+      self.indices(ondemand_js).is_ok()
+   }
+
+   fn animation_key(
+      &self,
+      key_bytes: &[u8],
+      indices: &OndemandIndices,
+      frames: &[Vec<Vec<i32>>],
+   ) -> Result<String, Error> {
+      Ok(
+         ClientTransaction::frame_selection(key_bytes, frames, indices)?
+            .state()?
+            .animation_key(),
+      )
+   }
+
+   fn generate(
+      &self,
+      key_bytes: &[u8],
+      animation_key: &str,
+      method: &str,
+      path: &str,
+      time: u32,
+   ) -> String {
+      Self::encode(key_bytes, animation_key, method, path, time, None)
+   }
+
+   fn generate_with_random_byte(
+      &self,
+      key_bytes: &[u8],
+      animation_key: &str,
+      method: &str,
+      path: &str,
+      time: u32,
+      random_byte: u8,
+   ) -> String {
+      Self::encode(
+         key_bytes,
+         animation_key,
+         method,
+         path,
+         time,
+         Some(random_byte),
+      )
+   }
+}
+
+/// Known algorithm versions.
+///
+/// [`Default`] holds [`V3`]. Later registrations take precedence, both in
+/// [`detect`](Self::detect) and for versions registered twice.
+#[derive(Clone)]
+pub struct Registry {
+   algorithms: Vec<Arc<dyn TransactionAlgorithm>>,
+}
+
+impl Default for Registry {
+   fn default() -> Self {
+      Self {
+         algorithms: vec![Arc::new(V3)],
+      }
+   }
+}
+
+impl Registry {
+   /// A registry without any algorithms.
+   #[must_use]
+   pub const fn empty() -> Self {
+      Self {
+         algorithms: Vec::new(),
+      }
+   }
+
+   /// Adds `algorithm`, replacing any registered for the same version.
+   pub fn register(&mut self, algorithm: Arc<dyn TransactionAlgorithm>) {
+      let version = algorithm.version();
+      self
+         .algorithms
+         .retain(|registered| registered.version() != version);
+      self.algorithms.push(algorithm);
+   }
+
+   /// The algorithm for `version`.
+   #[must_use]
+   pub fn get(&self, version: u8) -> Option<Arc<dyn TransactionAlgorithm>> {
+      self
+         .algorithms
+         .iter()
+         .find(|algorithm| algorithm.version() == version)
+         .cloned()
+   }
+
+   /// The most recently registered algorithm that `ondemand_js` implements.
+   #[must_use]
+   pub fn detect(&self, ondemand_js: &str) -> Option<Arc<dyn TransactionAlgorithm>> {
+      self
+         .algorithms
+         .iter()
+         .rev()
+         .find(|algorithm| algorithm.detect(ondemand_js))
+         .cloned()
+   }
+
+   /// This registry with `algorithm` added at the lowest priority, unless
+   /// its version is registered already.
+   #[cfg(feature = "fetch")]
+   pub(crate) fn with_fallback(&self, algorithm: Arc<dyn TransactionAlgorithm>) -> Self {
+      let mut registry = self.clone();
+      if registry.get(algorithm.version()).is_none() {
+         registry.algorithms.insert(0, algorithm);
+      }
+      registry
+   }
+
+   /// Registered versions, in registration order.
+   pub fn versions(&self) -> impl Iterator<Item = u8> {
+      self.algorithms.iter().map(|algorithm| algorithm.version())
+   }
+}
+
+impl fmt::Debug for Registry {
+   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      f.debug_struct("Registry")
+         .field("versions", &self.versions().collect::<Vec<_>>())
+         .finish()
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use std::{
+      collections::BTreeSet,
+      iter,
+   };
+
+   use super::*;
+   use crate::{
+      DecodedTransactionId,
+      canary::Fingerprint,
+      testing::SyntheticPages,
+   };
+
+   /// A made-up version 4 that marks its ondemand.js with a comment.
+   struct V4;
+
+   impl TransactionAlgorithm for V4 {
+      fn version(&self) -> u8 {
+         4
+      }
+
+      fn detect(&self, ondemand_js: &str) -> bool {
+         ondemand_js.contains("/* v4 */")
+      }
+
+      fn animation_key(
+         &self,
+         key_bytes: &[u8],
+         indices: &OndemandIndices,
+         frames: &[Vec<Vec<i32>>],
+      ) -> Result<String, Error> {
+         V3.animation_key(key_bytes, indices, frames)
+            .map(|key| key.to_uppercase())
+      }
+
+      fn generate(
+         &self,
+         key_bytes: &[u8],
+         animation_key: &str,
+         method: &str,
+         path: &str,
+         time: u32,
+      ) -> String {
+         format!(
+            "v4:{}",
+            V3.generate(key_bytes, animation_key, method, path, time)
+         )
+      }
+   }
+
+   /// V3's IDs, masked by the trait's default re-masking.
+   struct Remasked;
+
+   impl TransactionAlgorithm for Remasked {
+      fn version(&self) -> u8 {
+         3
+      }
+
+      fn detect(&self, ondemand_js: &str) -> bool {
+         V3.detect(ondemand_js)
+      }
+
+      fn animation_key(
+         &self,
+         key_bytes: &[u8],
+         indices: &OndemandIndices,
+         frames: &[Vec<Vec<i32>>],
+      ) -> Result<String, Error> {
+         V3.animation_key(key_bytes, indices, frames)
+      }
+
+      fn generate(
+         &self,
+         key_bytes: &[u8],
+         animation_key: &str,
+         method: &str,
+         path: &str,
+         time: u32,
+      ) -> String {
+         V3.generate(key_bytes, animation_key, method, path, time)
+      }
+   }
+
+   /// V3 with the last key byte index dropped and the row index shifted.
+   struct Shifted;
+
+   impl TransactionAlgorithm for Shifted {
+      fn version(&self) -> u8 {
+         3
+      }
+
+      fn detect(&self, ondemand_js: &str) -> bool {
+         V3.detect(ondemand_js)
+      }
+
+      fn indices(&self, ondemand_js: &str) -> Result<OndemandIndices, Error> {
+         let indices = OndemandIndices::parse(ondemand_js)?;
+         let key_byte_indices = indices
+            .key_byte_indices()
+            .split_last()
+            .map_or(&[][..], |(_, rest)| rest);
+         Ok(OndemandIndices::new(
+            indices.row_index() + 1,
+            key_byte_indices.to_vec(),
+         ))
+      }
+
+      fn animation_key(
+         &self,
+         key_bytes: &[u8],
+         indices: &OndemandIndices,
+         frames: &[Vec<Vec<i32>>],
+      ) -> Result<String, Error> {
+         V3.animation_key(key_bytes, indices, frames)
+      }
+
+      fn generate(
+         &self,
+         key_bytes: &[u8],
+         animation_key: &str,
+         method: &str,
+         path: &str,
+         time: u32,
+      ) -> String {
+         V3.generate(key_bytes, animation_key, method, path, time)
+      }
+   }
+
+   #[test]
+   fn default_detects_v3() {
+      let pages = SyntheticPages::new([0_u8; 48]);
+      let registry = Registry::default();
+      assert_eq!(registry.versions().collect::<Vec<_>>(), [3]);
+      assert_eq!(registry.detect(&pages.ondemand_js()).unwrap().version(), 3);
+      assert!(registry.detect("no indices").is_none());
+      assert!(Registry::empty().detect(&pages.ondemand_js()).is_none());
+   }
+
+   #[test]
+   fn random_sources() {
+      let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>());
+      let client = ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap();
+      let ids = |client: &ClientTransaction| {
+         iter::repeat_with(|| client.generate_transaction_id_at("GET", "/", 1_000))
+            .take(8)
+            .collect::<Vec<_>>()
+      };
+
+      let hash_derived = ids(&client);
+      assert!(hash_derived.iter().all(|id| *id == hash_derived[0]));
+
+      let seeded = client.clone().with_random_source(RandomSource::seeded(7));
+      let first = ids(&seeded);
+      assert_ne!(first, hash_derived);
+      assert_eq!(
+         ids(&client.clone().with_random_source(RandomSource::seeded(7))),
+         first
+      );
+
+      for source in [RandomSource::Os, RandomSource::ProcessRandom] {
+         let random = client.clone().with_random_source(source);
+         let masks = iter::repeat_with(|| random.generate_transaction_id_at("GET", "/", 1_000))
+            .take(64)
+            .map(|id| {
+               let decoded = DecodedTransactionId::decode(&id).unwrap();
+               assert_eq!(decoded.key_bytes, random.key_bytes());
+               assert_eq!(decoded.time, 1_000);
+               base64_decode_unpadded(&id).unwrap()[0]
+            })
+            .collect::<BTreeSet<_>>();
+         assert!(masks.len() > 16, "{:?}: {masks:?}", random.random_source());
+      }
+      for id in &first {
+         assert_eq!(
+            DecodedTransactionId::decode(id).unwrap().key_bytes,
+            client.key_bytes()
+         );
+      }
+
+      let remasked = client
+         .clone()
+         .with_algorithm(Arc::new(Remasked))
+         .with_random_source(RandomSource::seeded(7));
+      assert_eq!(ids(&remasked), first);
+      assert_eq!(
+         Remasked.generate(
+            client.key_bytes(),
+            client.animation_key(),
+            "GET",
+            "/",
+            1_000
+         ),
+         hash_derived[0]
+      );
+   }
+
+   #[test]
+   fn custom_versions() {
+      let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>());
+      let mut registry = Registry::default();
+      registry.register(Arc::new(V4));
+      assert_eq!(registry.versions().collect::<Vec<_>>(), [3, 4]);
+
+      let v3_js = pages.ondemand_js();
+      let v4_js = format!("/* v4 */{v3_js}");
+      assert_eq!(registry.detect(&v3_js).unwrap().version(), 3);
+      assert_eq!(registry.detect(&v4_js).unwrap().version(), 4);
+
+      let html = pages.home_page_html();
+      let v3 = ClientTransaction::new_with_registry(&html, &v3_js, &registry).unwrap();
+      let v4 = ClientTransaction::new_with_registry(&html, &v4_js, &registry).unwrap();
+      assert_eq!(v4.algorithm().version(), 4);
+      assert_eq!(v4.animation_key(), v3.animation_key().to_uppercase());
+      assert!(
+         v4.generate_transaction_id_at("GET", "/", 0)
+            .starts_with("v4:")
+      );
+
+      ClientTransaction::new_with_registry(&html, &v3_js, &Registry::empty()).unwrap_err();
+      let fallback = Registry::empty().with_fallback(Arc::new(V4));
+      assert_eq!(
+         registry
+            .with_fallback(Arc::new(V4))
+            .versions()
+            .collect::<Vec<_>>(),
+         [3, 4]
+      );
+      assert_eq!(
+         ClientTransaction::new_with_registry(&html, &v4_js, &fallback)
+            .unwrap()
+            .algorithm()
+            .version(),
+         4
+      );
+
+      registry.register(Arc::new(V3));
+      assert_eq!(registry.versions().collect::<Vec<_>>(), [4, 3]);
+      assert_eq!(registry.get(4).unwrap().version(), 4);
+      assert!(registry.get(2).is_none());
+   }
+
+   #[test]
+   fn custom_index_layouts() {
+      let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>());
+      let (html, js) = (pages.home_page_html(), pages.ondemand_js());
+      let mut registry = Registry::empty();
+      registry.register(Arc::new(Shifted));
+
+      let client = ClientTransaction::new_with_registry(&html, &js, &registry).unwrap();
+      let selection = ClientTransaction::select_frame_with_registry(&html, &js, &registry).unwrap();
+      assert_eq!(
+         selection.state().unwrap().animation_key(),
+         client.animation_key()
+      );
+      assert_ne!(
+         ClientTransaction::select_frame(&html, &js)
+            .unwrap()
+            .state()
+            .unwrap()
+            .animation_key(),
+         client.animation_key()
+      );
+
+      let fingerprint = Fingerprint::new_with_registry(&html, &js, &registry);
+      assert_eq!(
+         fingerprint.index_patterns,
+         Fingerprint::new(&html, &js).index_patterns - 1
+      );
+      assert!(fingerprint.generates);
+      assert_eq!(
+         Fingerprint::new_with_registry(&html, &js, &Registry::empty()).index_patterns,
+         0
+      );
+   }
+}
@@ -0,0 +1,212 @@
+//! Cubic bezier curve for animation timing.
+
+/// Method used to find the curve parameter for a given X (time).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Solver {
+   /// Bisection, as used by X's client. Slow but always converges.
+   #[default]
+   BinarySearch,
+   /// Newton-Raphson iteration, falling back to bisection where the slope is
+   /// too flat to converge.
+   NewtonRaphson,
+}
+
+/// Cubic bezier curve, like CSS `cubic-bezier()`.
+///
+/// The control points are `[x1, y1, x2, y2]`; the curve runs from `(0, 0)` to
+/// `(1, 1)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cubic {
+   curves: [f64; 4],
+   solver: Solver,
+}
+
+impl Cubic {
+   const EPSILON: f64 = 0.00001;
+   const NEWTON_ITERATIONS: usize = 8;
+   const NEWTON_MIN_SLOPE: f64 = 0.001;
+
+   #[must_use]
+   pub const fn new(curves: [f64; 4]) -> Self {
+      Self {
+         curves,
+         solver: Solver::BinarySearch,
+      }
+   }
+
+   /// Uses `solver` in [`value`](Self::value).
+   #[must_use]
+   pub const fn with_solver(mut self, solver: Solver) -> Self {
+      self.solver = solver;
+      self
+   }
+
+   /// Control points as `[x1, y1, x2, y2]`.
+   #[must_use]
+   pub const fn control_points(&self) -> [f64; 4] {
+      self.curves
+   }
+
+   #[must_use]
+   pub const fn solver(&self) -> Solver {
+      self.solver
+   }
+
+   /// Returns the Y value for a given X (time).
+   #[expect(clippy::float_cmp, reason = "checking boundary conditions")]
+   #[must_use]
+   pub fn value(&self, time: f64) -> f64 {
+      // Linear extrapolation outside [0, 1]
+      if time <= 0.0 {
+         let start_gradient = if self.curves[0] > 0.0 {
+            self.curves[1] / self.curves[0]
+         } else if self.curves[1] == 0.0 && self.curves[2] > 0.0 {
+            self.curves[3] / self.curves[2]
+         } else {
+            0.0
+         };
+         return start_gradient * time;
+      }
+
+      if time >= 1.0 {
+         let end_gradient = if self.curves[2] < 1.0 {
+            (self.curves[3] - 1.0) / (self.curves[2] - 1.0)
+         } else if self.curves[2] == 1.0 && self.curves[0] < 1.0 {
+            (self.curves[1] - 1.0) / (self.curves[0] - 1.0)
+         } else {
+            0.0
+         };
+         return 1.0 + end_gradient * (time - 1.0);
+      }
+
+      let param = match self.solver {
+         Solver::BinarySearch => self.solve_binary(time),
+         Solver::NewtonRaphson => self.solve_newton(time),
+      };
+
+      Self::bezier(self.curves[1], self.curves[3], param)
+   }
+
+   /// Finds the parameter that gives us target X by bisection.
+   fn solve_binary(&self, time: f64) -> f64 {
+      let mut low = 0.0_f64;
+      let mut high = 1.0_f64;
+      let mut mid;
+
+      loop {
+         mid = f64::midpoint(low, high);
+         let x_estimate = Self::bezier(self.curves[0], self.curves[2], mid);
+
+         if (time - x_estimate).abs() < Self::EPSILON {
+            return mid;
+         }
+
+         if (high - low).abs() < f64::EPSILON {
+            break;
+         }
+
+         if x_estimate < time {
+            low = mid;
+         } else {
+            high = mid;
+         }
+      }
+
+      mid
+   }
+
+   /// Finds the parameter that gives us target X by Newton-Raphson iteration.
+   fn solve_newton(&self, time: f64) -> f64 {
+      let mut param = time;
+
+      for _ in 0..Self::NEWTON_ITERATIONS {
+         let error = Self::bezier(self.curves[0], self.curves[2], param) - time;
+         if error.abs() < Self::EPSILON {
+            return param;
+         }
+
+         let slope = Self::bezier_slope(self.curves[0], self.curves[2], param);
+         if slope.abs() < Self::NEWTON_MIN_SLOPE {
+            break;
+         }
+
+         param -= error / slope;
+         if !(0.0..=1.0).contains(&param) {
+            break;
+         }
+      }
+
+      self.solve_binary(time)
+   }
+
+   /// Bezier formula: 3*p1*(1-t)²*t + 3*p2*(1-t)*t² + t³.
+   fn bezier(p1: f64, p2: f64, param: f64) -> f64 {
+      let complement = 1.0 - param;
+      let complement_sq = complement * complement;
+      let param_sq = param * param;
+
+      (3.0 * p1 * complement_sq).mul_add(
+         param,
+         (3.0 * p2 * complement).mul_add(param_sq, param_sq * param),
+      )
+   }
+
+   /// Derivative of [`bezier`](Self::bezier) with respect to the parameter:
+   /// 3*p1*(1-t)² + 6*(p2-p1)*(1-t)*t + 3*(1-p2)*t².
+   fn bezier_slope(p1: f64, p2: f64, param: f64) -> f64 {
+      let complement = 1.0 - param;
+
+      (3.0 * p1 * complement).mul_add(
+         complement,
+         (6.0 * (p2 - p1) * complement).mul_add(param, 3.0 * (1.0 - p2) * param * param),
+      )
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn cubic_curve_value() {
+      let cubic = Cubic::new([0.1, 0.2, 0.3, 0.4]);
+      let value = cubic.value(0.5);
+      assert!(value > 0.0);
+   }
+
+   #[test]
+   fn cubic_curve_boundaries() {
+      let cubic = Cubic::new([0.25, 0.1, 0.25, 1.0]);
+      assert!((cubic.value(0.0) - 0.0).abs() < f64::EPSILON);
+      assert!((cubic.value(1.0) - 1.0).abs() < 0.001);
+   }
+
+   #[test]
+   fn cubic_curve_extrapolation() {
+      // Curve with non-zero gradients at boundaries
+      let cubic = Cubic::new([0.4, 0.2, 0.6, 0.8]);
+      let below = cubic.value(-0.1);
+      let above = cubic.value(1.1);
+      assert!(below < 0.0);
+      assert!(above > 1.0);
+   }
+
+   #[test]
+   fn solvers_agree() {
+      let binary = Cubic::new([0.25, 0.1, 0.25, 1.0]);
+      let newton = binary.with_solver(Solver::NewtonRaphson);
+
+      for step in 1..100 {
+         let time = f64::from(step) / 100.0;
+         assert!((binary.value(time) - newton.value(time)).abs() < 0.0001);
+      }
+   }
+
+   #[test]
+   fn newton_flat_slope_falls_back() {
+      // Zero slope at t = 0.5 on the X curve.
+      let cubic = Cubic::new([1.0, 0.0, 0.0, 1.0]).with_solver(Solver::NewtonRaphson);
+      let binary = Cubic::new([1.0, 0.0, 0.0, 1.0]);
+      assert!((cubic.value(0.5) - binary.value(0.5)).abs() < 0.0001);
+   }
+}
@@ -16,6 +16,7 @@ pub fn interpolate(from: &[f64], to: &[f64], factor: f64) -> Result<Vec<f64>, Er
 }
 
 /// Lerp between two values: `from * (1 - factor) + to * factor`.
+#[must_use]
 pub fn lerp(from: f64, to: f64, factor: f64) -> f64 {
    from.mul_add(1.0 - factor, to * factor)
 }
@@ -0,0 +1,259 @@
+//! The loading animation behind the animation key.
+//!
+//! Each row of a `loading-x-anim` coordinate table describes a keyframe: a
+//! start and end color, an end rotation and a cubic-bezier timing curve. The
+//! animation key is the hex-encoded color and rotation matrix of that keyframe
+//! at a time derived from the verification key.
+//!
+//! ```
+//! use xitter_txid::animation::Keyframe;
+//!
+//! let keyframe = Keyframe::from_row(&[53, 185, 66, 54, 108, 77, 157, 86, 191, 195, 144]).unwrap();
+//! let state = keyframe.state_at(0.5);
+//! println!("rgb {:?}, {} degrees", state.color, state.rotation);
+//! println!("key {}", state.animation_key());
+//! ```
+
+mod cubic_curve;
+mod interpolate;
+mod render;
+mod rotation;
+
+pub use cubic_curve::{
+   Cubic,
+   Solver,
+};
+pub use interpolate::{
+   interpolate,
+   lerp,
+};
+pub use render::{
+   render_svg,
+   render_svg_sequence,
+};
+pub use rotation::rotation_matrix;
+
+use crate::{
+   error::Error,
+   utils::{
+      float_to_hex,
+      odd_coefficient,
+   },
+};
+
+/// Minimum number of values in a coordinate table row.
+pub const MIN_FRAME_VALUES: usize = 11;
+
+/// One row of a `loading-x-anim` coordinate table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe {
+   /// RGB color at the start of the animation.
+   pub from_color:  [f64; 3],
+   /// RGB color at the end of the animation.
+   pub to_color:    [f64; 3],
+   /// Rotation in degrees at the end of the animation. It starts at zero.
+   pub to_rotation: f64,
+   /// Timing curve mapping time to animation progress.
+   pub curve:       Cubic,
+}
+
+impl Keyframe {
+   /// Decodes a coordinate table row.
+   ///
+   /// Values 0-5 are the start and end colors, value 6 the end rotation and
+   /// values 7-10 the control points of the timing curve.
+   #[expect(
+      clippy::missing_asserts_for_indexing,
+      reason = "length check at function start ensures indices are valid"
+   )]
+   pub fn from_row(row: &[i32]) -> Result<Self, Error> {
+      if row.len() < MIN_FRAME_VALUES {
+         return Err(Error::Parse(format!(
+            "frame has {} values, need at least {MIN_FRAME_VALUES}",
+            row.len()
+         )));
+      }
+
+      let color = |values: &[i32]| [values[0], values[1], values[2]].map(f64::from);
+
+      let mut curves = [0.0; 4];
+      for (idx, (curve, &val)) in curves.iter_mut().zip(&row[7..11]).enumerate() {
+         *curve = solve(f64::from(val), odd_coefficient(idx), 1.0, false);
+      }
+
+      Ok(Self {
+         from_color:  color(&row[..3]),
+         to_color:    color(&row[3..6]),
+         to_rotation: solve(f64::from(row[6]), 60.0, 360.0, true),
+         curve:       Cubic::new(curves),
+      })
+   }
+
+   /// Interpolates the keyframe at `time`, where `0.0` is the start and `1.0`
+   /// the end of the animation.
+   #[must_use]
+   pub fn state_at(&self, time: f64) -> AnimationState {
+      let progress = self.curve.value(time);
+
+      let mut color = [0.0; 3];
+      for (value, (&from, &to)) in color
+         .iter_mut()
+         .zip(self.from_color.iter().zip(&self.to_color))
+      {
+         *value = lerp(from, to, progress).clamp(0.0, 255.0);
+      }
+
+      AnimationState {
+         time,
+         progress,
+         color,
+         rotation: lerp(0.0, self.to_rotation, progress),
+      }
+   }
+}
+
+/// Interpolated values of a [`Keyframe`] at one point in time.
+#[expect(
+   clippy::module_name_repetitions,
+   reason = "`State` alone is ambiguous when re-exported"
+)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationState {
+   /// Time the state was computed for.
+   pub time:     f64,
+   /// Output of the timing curve at `time`.
+   pub progress: f64,
+   /// RGB color, clamped to `0.0..=255.0`.
+   pub color:    [f64; 3],
+   /// Rotation in degrees.
+   pub rotation: f64,
+}
+
+impl AnimationState {
+   /// Rotation as a 2x2 matrix: `[cos, -sin, sin, cos]`.
+   #[must_use]
+   pub fn rotation_matrix(&self) -> [f64; 4] {
+      rotation_matrix(self.rotation)
+   }
+
+   /// Encodes the state as an animation key: the color as hex bytes, then
+   /// the rotation matrix as hex fractions, with signs and points dropped.
+   #[expect(
+      clippy::cast_possible_truncation,
+      reason = "color values are clamped to 0-255"
+   )]
+   #[must_use]
+   pub fn animation_key(&self) -> String {
+      let mut parts = Vec::with_capacity(9);
+
+      for val in self.color {
+         parts.push(format!("{:x}", val.round() as i32));
+      }
+
+      for val in self.rotation_matrix() {
+         let rounded = (val * 100.0).round() / 100.0;
+         let hex = float_to_hex(rounded.abs());
+
+         if hex.starts_with('.') {
+            parts.push(format!("0{}", hex.to_lowercase()));
+         } else if hex.is_empty() {
+            parts.push("0".to_owned());
+         } else {
+            parts.push(hex.to_lowercase());
+         }
+      }
+
+      parts.push("0".to_owned());
+      parts.push("0".to_owned());
+
+      parts.join("").replace(['.', '-'], "")
+   }
+}
+
+/// The part of the loading animation a verification key selects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameSelection {
+   /// Which `loading-x-anim-N` SVG the coordinate table came from.
+   pub frame_index: usize,
+   /// Which row of the coordinate table was used.
+   pub row_index:   usize,
+   /// Values of that row.
+   pub row:         Vec<i32>,
+   /// Time the row's keyframe is sampled at, as a fraction of the animation.
+   pub target_time: f64,
+}
+
+impl FrameSelection {
+   /// Decodes the selected row.
+   pub fn keyframe(&self) -> Result<Keyframe, Error> {
+      Keyframe::from_row(&self.row)
+   }
+
+   /// Interpolates the selected keyframe at the target time.
+   pub fn state(&self) -> Result<AnimationState, Error> {
+      Ok(self.keyframe()?.state_at(self.target_time))
+   }
+}
+
+/// Scales a byte value from `0..=255` to `min_val..=max_val`.
+fn solve(value: f64, min_val: f64, max_val: f64, rounding: bool) -> f64 {
+   let result = value.mul_add((max_val - min_val) / 255.0, min_val);
+   if rounding {
+      result.floor()
+   } else {
+      (result * 100.0).round() / 100.0
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   const ROW: [i32; 11] = [53, 185, 66, 54, 108, 77, 157, 86, 191, 195, 144];
+
+   fn assert_color(actual: [f64; 3], expected: [f64; 3]) {
+      for (actual, expected) in actual.into_iter().zip(expected) {
+         assert!((actual - expected).abs() < 0.001, "{actual} != {expected}");
+      }
+   }
+
+   #[test]
+   fn keyframe_insufficient_values() {
+      Keyframe::from_row(&[1, 2, 3]).unwrap_err();
+   }
+
+   #[test]
+   fn keyframe_from_row() {
+      let keyframe = Keyframe::from_row(&ROW).unwrap();
+      assert_color(keyframe.from_color, [53.0, 185.0, 66.0]);
+      assert_color(keyframe.to_color, [54.0, 108.0, 77.0]);
+      assert!((keyframe.to_rotation - 244.0).abs() < f64::EPSILON);
+   }
+
+   #[test]
+   fn state_at_endpoints() {
+      let keyframe = Keyframe::from_row(&ROW).unwrap();
+
+      let start = keyframe.state_at(0.0);
+      assert_color(start.color, keyframe.from_color);
+      assert!(start.rotation.abs() < f64::EPSILON);
+
+      let end = keyframe.state_at(1.0);
+      assert_color(end.color, keyframe.to_color);
+      assert!((end.rotation - keyframe.to_rotation).abs() < 0.001);
+   }
+
+   #[test]
+   fn animation_key_at_start() {
+      // No rotation: the matrix is [1, 0, 0, 1].
+      let state = Keyframe::from_row(&ROW).unwrap().state_at(0.0);
+      assert_eq!(state.animation_key(), "35b942100100");
+   }
+
+   #[test]
+   fn solve_scaling() {
+      assert!((solve(0.0, 60.0, 360.0, true) - 60.0).abs() < f64::EPSILON);
+      assert!((solve(255.0, 60.0, 360.0, true) - 360.0).abs() < f64::EPSILON);
+      assert!((solve(255.0, -1.0, 1.0, false) - 1.0).abs() < f64::EPSILON);
+   }
+}
@@ -0,0 +1,146 @@
+//! SVG rendering of a keyframe, for comparing against X's loading animation.
+
+use std::fmt::Write as _;
+
+use super::{
+   AnimationState,
+   Keyframe,
+};
+
+/// Side length of each panel, in SVG user units.
+const PANEL: f64 = 100.0;
+
+/// Renders the frame path `path_d` as X animates it with `keyframe` at
+/// `time`, as a two-panel SVG.
+///
+/// `path_d` is the `d` attribute of the selected `loading-x-anim-N` SVG, as
+/// returned by [`animation_paths`](crate::parse::animation_paths). The left
+/// panel plots the timing curve with a marker at `time`; the right panel
+/// draws the path in the interpolated color, rotated by the interpolated
+/// angle about its center.
+#[must_use]
+pub fn render_svg(path_d: &str, keyframe: &Keyframe, time: f64) -> String {
+   let state = keyframe.state_at(time);
+   let [x1, y1, x2, y2] = keyframe.curve.control_points();
+
+   let mut svg = String::new();
+   let _ = write!(
+      svg,
+      "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {width} {height}\" \
+       width=\"{width}\" height=\"{height}\" font-family=\"monospace\" font-size=\"5\">",
+      width = PANEL * 2.0,
+      height = PANEL + 20.0,
+   );
+   svg.push_str("<rect width=\"100%\" height=\"100%\" fill=\"white\"/>");
+
+   // Timing curve, with Y pointing up.
+   let _ = write!(
+      svg,
+      "<rect x=\"0\" y=\"0\" width=\"{PANEL}\" height=\"{PANEL}\" fill=\"none\" \
+       stroke=\"#ccc\"/><path d=\"M 0,{PANEL} C {},{} {},{} {PANEL},0\" fill=\"none\" \
+       stroke=\"black\"/>",
+      x1 * PANEL,
+      (1.0 - y1) * PANEL,
+      x2 * PANEL,
+      (1.0 - y2) * PANEL,
+   );
+   let _ = write!(
+      svg,
+      "<line x1=\"{x}\" y1=\"0\" x2=\"{x}\" y2=\"{PANEL}\" stroke=\"#e44\" \
+       stroke-dasharray=\"2\"/><circle cx=\"{x}\" cy=\"{y}\" r=\"2\" fill=\"#e44\"/>",
+      x = time * PANEL,
+      y = (1.0 - state.progress) * PANEL,
+   );
+
+   // The frame path, scaled into the right panel and rotated about the
+   // center of its bounding box.
+   let (min, max) = path_bounds(path_d);
+   let center = f64::midpoint(min, max);
+   let _ = write!(
+      svg,
+      "<svg x=\"{PANEL}\" y=\"0\" width=\"{PANEL}\" height=\"{PANEL}\" viewBox=\"{min} {min} \
+       {size} {size}\"><g transform=\"rotate({rotation} {center} {center})\"><path d=\"{path}\" \
+       fill=\"{color}\" stroke=\"{color}\"/></g></svg>",
+      size = max - min,
+      rotation = state.rotation,
+      path = path_d.replace(['"', '<', '>', '&'], ""),
+      color = css_color(&state),
+   );
+
+   let _ = write!(
+      svg,
+      "<text x=\"2\" y=\"{}\">t={:.4} p={:.4} {} {:.2}deg key={}</text></svg>",
+      PANEL + 12.0,
+      state.time,
+      state.progress,
+      css_color(&state),
+      state.rotation,
+      state.animation_key(),
+   );
+
+   svg
+}
+
+/// Renders `steps + 1` evenly spaced frames from the start to the end of the
+/// animation.
+#[must_use]
+pub fn render_svg_sequence(path_d: &str, keyframe: &Keyframe, steps: u32) -> Vec<String> {
+   let steps = steps.max(1);
+   (0..=steps)
+      .map(|step| render_svg(path_d, keyframe, f64::from(step) / f64::from(steps)))
+      .collect()
+}
+
+fn css_color(state: &AnimationState) -> String {
+   let [red, green, blue] = state.color.map(f64::round);
+   format!("rgb({red},{green},{blue})")
+}
+
+/// Smallest and largest coordinate in `path_d`, padded by one unit, so the
+/// path fits a square view box.
+fn path_bounds(path_d: &str) -> (f64, f64) {
+   let (min, max) = path_d
+      .replace(
+         |chr: char| !chr.is_ascii_digit() && !matches!(chr, '-' | '.'),
+         " ",
+      )
+      .split_whitespace()
+      .filter_map(|token| token.parse::<f64>().ok())
+      .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+         (min.min(value), max.max(value))
+      });
+
+   if min.is_finite() {
+      (min - 1.0, max + 1.0)
+   } else {
+      (0.0, 1.0)
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   const ROW: [i32; 11] = [53, 185, 66, 54, 108, 77, 157, 86, 191, 195, 144];
+   const PATH: &str = "M 10,30 C53 185 66 54 108 77 157 86 191 195 144";
+
+   #[test]
+   fn render_contains_state() {
+      let keyframe = Keyframe::from_row(&ROW).unwrap();
+      let svg = render_svg(PATH, &keyframe, 0.0);
+      assert!(svg.starts_with("<svg"));
+      assert!(svg.ends_with("</svg>"));
+      assert!(svg.contains("viewBox=\"9 9 187 187\""));
+      assert!(svg.contains("<g transform=\"rotate(0 102.5 102.5)\">"));
+      assert!(svg.contains(&format!("<path d=\"{PATH}\" fill=\"rgb(53,185,66)\"")));
+      assert!(svg.contains("key=35b942100100"));
+   }
+
+   #[test]
+   fn render_sequence_endpoints() {
+      let keyframe = Keyframe::from_row(&ROW).unwrap();
+      let frames = render_svg_sequence(PATH, &keyframe, 4);
+      assert_eq!(frames.len(), 5);
+      assert!(frames[4].contains("fill=\"rgb(54,108,77)\""));
+   }
+}
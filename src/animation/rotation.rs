@@ -1,6 +1,7 @@
 //! 2D rotation matrix.
 
 /// Converts degrees to a 2x2 rotation matrix: `[cos, -sin, sin, cos]`.
+#[must_use]
 pub fn rotation_matrix(degrees: f64) -> [f64; 4] {
    let radians = degrees.to_radians();
    let cos = radians.cos();
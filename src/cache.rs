@@ -0,0 +1,301 @@
+//! On-disk cache for the homepage and ondemand.s chunk.
+
+use std::{
+   fs,
+   io,
+   path::{
+      Path,
+      PathBuf,
+   },
+   process,
+   sync::atomic::{
+      AtomicU64,
+      Ordering,
+   },
+   time::{
+      Duration,
+      SystemTime,
+      UNIX_EPOCH,
+   },
+};
+
+use data_encoding::HEXLOWER;
+use hmac_sha256::Hash;
+use minreq::Method;
+
+use crate::{
+   discovery::OndemandCandidate,
+   error::Error,
+   fetch::{
+      FetchOptions,
+      Fetched,
+      Page,
+   },
+};
+
+const HOME_PREFIX: &str = "home-";
+const ONDEMAND_PREFIX: &str = "ondemand-";
+
+/// Distinguishes the temporary files of concurrent writes in one process.
+static WRITE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// File cache for the pages
+/// [`ClientTransaction::fetch_with`](crate::ClientTransaction::fetch_with)
+/// downloads.
+///
+/// The homepage is keyed by its URL and cookies and reused for
+/// [`ttl`](Self::ttl); after that it is revalidated with `If-None-Match` /
+/// `If-Modified-Since`. The ondemand.s chunk is keyed by its hash, which
+/// changes whenever its content does, so a cached copy never expires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileCache {
+   /// Directory the entries are stored in. Created on first write.
+   pub dir:            PathBuf,
+   /// How long a cached homepage is used without asking the server.
+   pub ttl:            Duration,
+   /// Entries kept per kind (homepages and ondemand chunks); the least
+   /// recently written are removed first. `0` keeps every entry.
+   pub max_entries:    usize,
+   /// Serves a stale homepage when it can't be fetched or revalidated.
+   pub stale_on_error: bool,
+}
+
+impl FileCache {
+   /// A cache in `dir` with a one hour TTL, keeping 8 entries per kind.
+   pub fn new(dir: impl Into<PathBuf>) -> Self {
+      Self {
+         dir:            dir.into(),
+         ttl:            Duration::from_hours(1),
+         max_entries:    8,
+         stale_on_error: true,
+      }
+   }
+
+   /// Removes every entry from [`dir`](Self::dir).
+   pub fn clear(&self) -> io::Result<()> {
+      let entries = match fs::read_dir(&self.dir) {
+         Ok(entries) => entries,
+         Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+         Err(err) => return Err(err),
+      };
+
+      for entry in entries {
+         let path = entry?.path();
+         if is_entry_file(&path) {
+            fs::remove_file(path)?;
+         }
+      }
+      Ok(())
+   }
+
+   /// Returns the homepage, from the cache while it is fresh.
+   pub(crate) fn get_home(&self, options: &FetchOptions) -> Result<Page, Error> {
+      let mut key_input = options.home_url.clone();
+      if let Some(ref cookies) = options.cookies {
+         key_input.push('\n');
+         key_input.push_str(cookies);
+      }
+      let digest = Hash::hash(key_input.as_bytes());
+      let key = format!("{HOME_PREFIX}{}", HEXLOWER.encode(&digest[..8]));
+
+      let mut cached = self.load(&key);
+      if let Some(page) = cached.take_if(|page| age(page) < self.ttl) {
+         return Ok(page);
+      }
+
+      let mut validators = Vec::new();
+      if let Some(ref page) = cached {
+         if let Some(ref etag) = page.etag {
+            validators.push(("If-None-Match", etag.as_str()));
+         }
+         if let Some(ref last_modified) = page.last_modified {
+            validators.push(("If-Modified-Since", last_modified.as_str()));
+         }
+      }
+
+      match (
+         options.send(&Method::Get, &options.home_url, "x.com", &validators),
+         cached,
+      ) {
+         (Ok(Fetched::Page(page)), _) => {
+            self.store(&key, &page);
+            Ok(page)
+         },
+         (Ok(Fetched::NotModified { date }), Some(page)) => {
+            let page = Page {
+               date,
+               fetched_at: SystemTime::now(),
+               ..page
+            };
+            self.store(&key, &page);
+            Ok(page)
+         },
+         (Ok(Fetched::NotModified { .. }), None) => Err(Error::HttpStatus(304, "x.com")),
+         (Err(_), Some(page)) if self.stale_on_error => Ok(page),
+         (Err(err), _) => Err(err),
+      }
+   }
+
+   /// Returns the ondemand.s chunk at `candidate`'s URL, from the cache if
+   /// it was fetched before. Chunks are keyed by hash, or by URL if the
+   /// candidate has none.
+   pub(crate) fn get_ondemand(
+      &self,
+      options: &FetchOptions,
+      candidate: &OndemandCandidate,
+   ) -> Result<Page, Error> {
+      let key = match candidate.hash {
+         Some(ref hash) if hash.bytes().all(|byte| byte.is_ascii_alphanumeric()) => {
+            format!("{ONDEMAND_PREFIX}{hash}")
+         },
+         ref hash => {
+            let digest = Hash::hash(hash.as_deref().unwrap_or(&candidate.url).as_bytes());
+            format!("{ONDEMAND_PREFIX}{}", HEXLOWER.encode(&digest[..8]))
+         },
+      };
+
+      if let Some(page) = self.load(&key) {
+         return Ok(page);
+      }
+
+      let page = options.get_page(&candidate.url, "ondemand.js")?;
+      self.store(&key, &page);
+      Ok(page)
+   }
+
+   fn load(&self, key: &str) -> Option<Page> {
+      let meta = fs::read_to_string(self.dir.join(format!("{key}.meta"))).ok()?;
+      let body = fs::read_to_string(self.dir.join(format!("{key}.body"))).ok()?;
+
+      let mut page = Page {
+         body,
+         etag: None,
+         last_modified: None,
+         date: None,
+         fetched_at: UNIX_EPOCH,
+      };
+      let mut fetched_at = None;
+      let mut digest = None;
+      for line in meta.lines() {
+         match line.split_once(" = ") {
+            Some(("fetched_at", value)) => fetched_at = value.parse::<u64>().ok(),
+            Some(("digest", value)) => digest = Some(value),
+            Some(("etag", value)) => page.etag = Some(value.to_owned()),
+            Some(("last_modified", value)) => page.last_modified = Some(value.to_owned()),
+            Some(("date", value)) => page.date = Some(value.to_owned()),
+            _ => {},
+         }
+      }
+
+      // A body replaced after its meta was read belongs to another fetch.
+      (digest? == body_digest(&page.body)).then_some(())?;
+
+      page.fetched_at = UNIX_EPOCH.checked_add(Duration::from_secs(fetched_at?))?;
+      Some(page)
+   }
+
+   /// Writes an entry and evicts old ones. Failures only cost a refetch
+   /// later, so they are ignored.
+   ///
+   /// Each file is written to a temporary file and renamed into place, meta
+   /// last, so readers never see a partial file. The meta records the body's
+   /// digest, so a body paired with another write's meta is not used.
+   fn store(&self, key: &str, page: &Page) {
+      let mut meta = format!(
+         "fetched_at = {}\ndigest = {}\n",
+         page
+            .fetched_at
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs()),
+         body_digest(&page.body)
+      );
+      if let Some(ref etag) = page.etag {
+         meta.push_str("etag = ");
+         meta.push_str(etag);
+         meta.push('\n');
+      }
+      if let Some(ref last_modified) = page.last_modified {
+         meta.push_str("last_modified = ");
+         meta.push_str(last_modified);
+         meta.push('\n');
+      }
+      if let Some(ref date) = page.date {
+         meta.push_str("date = ");
+         meta.push_str(date);
+         meta.push('\n');
+      }
+
+      let written = fs::create_dir_all(&self.dir)
+         .and_then(|()| self.write_atomic(&format!("{key}.body"), &page.body))
+         .and_then(|()| self.write_atomic(&format!("{key}.meta"), &meta));
+      if written.is_err() {
+         return;
+      }
+
+      let prefix = if key.starts_with(HOME_PREFIX) {
+         HOME_PREFIX
+      } else {
+         ONDEMAND_PREFIX
+      };
+      self.evict(prefix);
+   }
+
+   /// Writes `contents` to a temporary file in [`dir`](Self::dir) and
+   /// renames it to `name`.
+   fn write_atomic(&self, name: &str, contents: &str) -> io::Result<()> {
+      let temp = self.dir.join(format!(
+         "{name}.{}-{}.tmp",
+         process::id(),
+         WRITE_COUNTER.fetch_add(1, Ordering::Relaxed)
+      ));
+      fs::write(&temp, contents)
+         .and_then(|()| fs::rename(&temp, self.dir.join(name)))
+         .inspect_err(|_| {
+            let _ = fs::remove_file(&temp);
+         })
+   }
+
+   /// Removes all but the [`max_entries`](Self::max_entries) most recently
+   /// written entries whose key starts with `prefix`.
+   fn evict(&self, prefix: &str) {
+      if self.max_entries == 0 {
+         return;
+      }
+      let Ok(dir) = fs::read_dir(&self.dir) else {
+         return;
+      };
+
+      let mut entries = dir
+         .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().into_string().ok()?;
+            let key = name.strip_suffix(".meta")?;
+            let written = entry.metadata().and_then(|meta| meta.modified()).ok()?;
+            key.starts_with(prefix).then(|| (written, key.to_owned()))
+         })
+         .collect::<Vec<_>>();
+      entries.sort_unstable_by(|left, right| right.cmp(left));
+
+      for &(_, ref key) in entries.iter().skip(self.max_entries) {
+         let _ = fs::remove_file(self.dir.join(format!("{key}.meta")));
+         let _ = fs::remove_file(self.dir.join(format!("{key}.body")));
+      }
+   }
+}
+
+/// Time since `page` was last fetched or revalidated.
+fn age(page: &Page) -> Duration {
+   page.fetched_at.elapsed().unwrap_or_default()
+}
+
+fn body_digest(body: &str) -> String {
+   HEXLOWER.encode(&Hash::hash(body.as_bytes())[..8])
+}
+
+fn is_entry_file(path: &Path) -> bool {
+   let name = path.file_name().and_then(|name| name.to_str());
+   let extension = path.extension().and_then(|extension| extension.to_str());
+
+   name.is_some_and(|name| name.starts_with(HOME_PREFIX) || name.starts_with(ONDEMAND_PREFIX))
+      && matches!(extension, Some("body" | "meta" | "tmp"))
+}
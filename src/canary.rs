@@ -0,0 +1,322 @@
+//! Detecting changes to X's pages that would break the algorithm.
+//!
+//! A [`Fingerprint`] records the structure each extraction stage finds in the
+//! homepage and ondemand.js, but none of the per-deployment values: how many
+//! animation frames there are, how many coordinates each of their rows has,
+//! how many index patterns ondemand.js holds, and so on. The hash salt never
+//! appears in either page, so only [`generates`](Fingerprint::generates)
+//! covers it. Comparing it with
+//! the fingerprint of pages known to work shows what drifted:
+//!
+//! ```ignore
+//! let known_good: Fingerprint = fs::read_to_string("canary.txt")?.parse()?;
+//! let current = canary::fetch(&FetchOptions::default())?;
+//! for drift in current.diff(&known_good) {
+//!    eprintln!("{drift}");
+//! }
+//! ```
+//!
+//! The `canary` example runs this check from the command line.
+
+use std::{
+   fmt,
+   str::FromStr,
+};
+
+#[cfg(feature = "fetch")] use crate::fetch::FetchOptions;
+use crate::{
+   algorithm::Registry,
+   decode::DecodedTransactionId,
+   discovery::{
+      self,
+      Strategy,
+   },
+   error::Error,
+   parse,
+   transaction::ClientTransaction,
+};
+
+/// Request the generation stage is checked with.
+const PROBE: (&str, &str) = ("GET", "/i/api/1.1/jot/client_event.json");
+
+/// Structure found by each extraction stage.
+///
+/// Formats as `key = value` lines and parses back from the same form, so a
+/// known-good fingerprint can be kept in a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+   /// Decoded length of the verification key, or 0 if it's missing.
+   pub key_length:        usize,
+   /// Number of `loading-x-anim` frames.
+   pub frames:            usize,
+   /// Coordinate count of every row, frame by frame.
+   pub row_lengths:       Vec<usize>,
+   /// Strategy that found the most likely ondemand.s URL.
+   pub ondemand_strategy: Option<Strategy>,
+   /// Number of indices ondemand.js holds, the row index included; for
+   /// [`V3`](crate::algorithm::V3), its `(e[N], 16)` patterns.
+   pub index_patterns:    usize,
+   /// Whether a client could be built and its IDs decode with its
+   /// algorithm's version byte.
+   pub generates:         bool,
+}
+
+impl Fingerprint {
+   /// Runs every extraction stage over the pages. Failing stages are
+   /// recorded as zero, empty or `false` rather than returned.
+   #[must_use]
+   pub fn new(home_page_html: &str, ondemand_js: &str) -> Self {
+      Self::new_with_registry(home_page_html, ondemand_js, &Registry::default())
+   }
+
+   /// Like [`new`](Self::new), reading indices and generating IDs with the
+   /// algorithm version `registry` [detects](Registry::detect).
+   #[must_use]
+   pub fn new_with_registry(home_page_html: &str, ondemand_js: &str, registry: &Registry) -> Self {
+      let frames = parse::animation_frames(home_page_html);
+
+      let generates = ClientTransaction::new_with_registry(home_page_html, ondemand_js, registry)
+         .is_ok_and(|client| {
+            let id = client.generate_transaction_id(PROBE.0, PROBE.1);
+            DecodedTransactionId::decode(&id)
+               .is_ok_and(|decoded| decoded.version == client.algorithm().version())
+         });
+
+      Self {
+         key_length: parse::verification_key(home_page_html)
+            .and_then(|key| parse::decode_verification_key(&key))
+            .map_or(0, |key| key.len()),
+         frames: frames.len(),
+         row_lengths: frames.iter().flatten().map(Vec::len).collect(),
+         ondemand_strategy: discovery::ondemand_candidates(home_page_html)
+            .first()
+            .map(|candidate| candidate.strategy),
+         index_patterns: registry
+            .detect(ondemand_js)
+            .and_then(|algorithm| algorithm.indices(ondemand_js).ok())
+            .map_or(0, |indices| 1 + indices.key_byte_indices().len()),
+         generates,
+      }
+   }
+
+   /// Differences from `known_good`, empty if nothing drifted.
+   #[must_use]
+   pub fn diff(&self, known_good: &Self) -> Vec<Drift> {
+      known_good
+         .fields()
+         .into_iter()
+         .zip(self.fields())
+         .filter(|&((_, ref expected), (_, ref found))| expected != found)
+         .map(|((field, expected), (_, found))| {
+            Drift {
+               field,
+               expected,
+               found,
+            }
+         })
+         .collect()
+   }
+
+   /// Field names and formatted values, in declaration order.
+   fn fields(&self) -> [(&'static str, String); 6] {
+      let row_lengths = self
+         .row_lengths
+         .iter()
+         .map(ToString::to_string)
+         .collect::<Vec<_>>()
+         .join(" ");
+
+      [
+         ("key_length", self.key_length.to_string()),
+         ("frames", self.frames.to_string()),
+         ("row_lengths", row_lengths),
+         (
+            "ondemand_strategy",
+            self
+               .ondemand_strategy
+               .map_or_else(|| "none".to_owned(), |strategy| strategy.to_string()),
+         ),
+         ("index_patterns", self.index_patterns.to_string()),
+         ("generates", self.generates.to_string()),
+      ]
+   }
+}
+
+/// Fetches the current homepage and ondemand.js and fingerprints them with
+/// the options' [`registry`](FetchOptions::registry).
+///
+/// A homepage without an ondemand.s hash is fingerprinted with an empty
+/// ondemand.js; only network and HTTP errors are returned.
+#[cfg(feature = "fetch")]
+pub fn fetch(options: &FetchOptions) -> Result<Fingerprint, Error> {
+   let home_page_html = options.get_home()?.body;
+   let ondemand_js = match options.get_ondemand(&home_page_html) {
+      Ok((_, ondemand)) => ondemand.body,
+      Err(Error::MissingKey(_)) => String::new(),
+      Err(err) => return Err(err),
+   };
+   Ok(Fingerprint::new_with_registry(
+      &home_page_html,
+      &ondemand_js,
+      &options.registry,
+   ))
+}
+
+impl fmt::Display for Fingerprint {
+   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      for (field, value) in self.fields() {
+         writeln!(f, "{field} = {value}")?;
+      }
+      Ok(())
+   }
+}
+
+impl FromStr for Fingerprint {
+   type Err = Error;
+
+   fn from_str(s: &str) -> Result<Self, Self::Err> {
+      let mut fingerprint = Self {
+         key_length:        0,
+         frames:            0,
+         row_lengths:       Vec::new(),
+         ondemand_strategy: None,
+         index_patterns:    0,
+         generates:         false,
+      };
+
+      for line in s
+         .lines()
+         .map(str::trim)
+         .filter(|line| !line.is_empty() && !line.starts_with('#'))
+      {
+         let (field, value) = line
+            .split_once(" = ")
+            .or_else(|| line.strip_suffix(" =").map(|field| (field, "")))
+            .ok_or_else(|| Error::Parse(format!("invalid fingerprint line `{line}`")))?;
+
+         match field {
+            "key_length" => fingerprint.key_length = parse_value(field, value)?,
+            "frames" => fingerprint.frames = parse_value(field, value)?,
+            "row_lengths" => {
+               fingerprint.row_lengths = value
+                  .split_whitespace()
+                  .map(|length| parse_value(field, length))
+                  .collect::<Result<_, _>>()?;
+            },
+            "ondemand_strategy" => {
+               fingerprint.ondemand_strategy = match value {
+                  "none" => None,
+                  strategy => Some(strategy.parse()?),
+               };
+            },
+            "index_patterns" => fingerprint.index_patterns = parse_value(field, value)?,
+            "generates" => fingerprint.generates = parse_value(field, value)?,
+            _ => return Err(Error::Parse(format!("unknown fingerprint field `{field}`"))),
+         }
+      }
+
+      Ok(fingerprint)
+   }
+}
+
+fn parse_value<T: FromStr>(field: &str, value: &str) -> Result<T, Error> {
+   value
+      .parse()
+      .map_err(|_| Error::Parse(format!("invalid {field} `{value}`")))
+}
+
+/// A fingerprint field that differs from the known-good one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+   pub field:    &'static str,
+   pub expected: String,
+   pub found:    String,
+}
+
+impl fmt::Display for Drift {
+   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      write!(
+         f,
+         "{}: expected `{}`, found `{}`",
+         self.field, self.expected, self.found
+      )
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::testing::SyntheticPages;
+
+   fn pages() -> SyntheticPages {
+      SyntheticPages::new((0..48).collect::<Vec<u8>>()).with_indices(2, vec![12, 14, 7])
+   }
+
+   #[test]
+   fn synthetic_pages() {
+      let pages = pages();
+      let fingerprint = Fingerprint::new(&pages.home_page_html(), &pages.ondemand_js());
+
+      assert_eq!(fingerprint.key_length, 48);
+      assert_eq!(fingerprint.frames, 4);
+      assert_eq!(
+         fingerprint.row_lengths.len(),
+         parse::animation_frames(&pages.home_page_html())
+            .iter()
+            .map(Vec::len)
+            .sum::<usize>()
+      );
+      assert_eq!(fingerprint.ondemand_strategy, Some(Strategy::NamedChunkMap));
+      assert_eq!(fingerprint.index_patterns, 4);
+      assert!(fingerprint.generates);
+   }
+
+   #[test]
+   fn detects_drift() {
+      let pages = pages();
+      let known_good = Fingerprint::new(&pages.home_page_html(), &pages.ondemand_js());
+      assert!(known_good.diff(&known_good).is_empty());
+
+      let js = pages.ondemand_js().replace("parseInt(e[12], 16),", "");
+      let current = Fingerprint::new("<html>login</html>", &js);
+      let drift = current.diff(&known_good);
+      let fields = drift.iter().map(|drift| drift.field).collect::<Vec<_>>();
+      assert_eq!(fields, [
+         "key_length",
+         "frames",
+         "row_lengths",
+         "ondemand_strategy",
+         "index_patterns",
+         "generates"
+      ]);
+      assert_eq!(drift[0].to_string(), "key_length: expected `48`, found `0`");
+
+      // One row changing length is drift even if another row has the new length.
+      let mut shortened = known_good.clone();
+      let other = shortened.row_lengths[1];
+      shortened.row_lengths[0] = other - 1;
+      let mut lengthened = known_good.clone();
+      lengthened.row_lengths[0] += 1;
+      for current in [shortened, lengthened] {
+         let drift = current.diff(&known_good);
+         assert_eq!(drift.len(), 1);
+         assert_eq!(drift[0].field, "row_lengths");
+      }
+   }
+
+   #[test]
+   fn text_round_trip() {
+      let pages = pages();
+      let fingerprint = Fingerprint::new(&pages.home_page_html(), &pages.ondemand_js());
+      assert_eq!(
+         fingerprint.to_string().parse::<Fingerprint>().unwrap(),
+         fingerprint
+      );
+
+      let empty = Fingerprint::new("", "");
+      assert_eq!(empty.to_string().parse::<Fingerprint>().unwrap(), empty);
+
+      "frames = many".parse::<Fingerprint>().unwrap_err();
+      "colour = blue".parse::<Fingerprint>().unwrap_err();
+   }
+}
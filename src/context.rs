@@ -0,0 +1,120 @@
+//! Everything a request to X's web API needs from X's own pages.
+
+#[cfg(feature = "fetch")] use crate::fetch::FetchOptions;
+use crate::{
+   error::Error,
+   graphql::Catalog,
+   manifest::ChunkManifest,
+   parse,
+   transaction::ClientTransaction,
+};
+
+/// A [`ClientTransaction`] with the bearer token and GraphQL operations from
+/// main.js.
+///
+/// Getting these takes a third download on top of the homepage and
+/// ondemand.js, so use [`ClientTransaction::fetch_with`] instead when only
+/// transaction IDs are needed.
+pub struct WebClientContext {
+   /// Transaction ID generator.
+   pub transaction:  ClientTransaction,
+   /// Web client's bearer token, sent as `authorization: Bearer <token>`.
+   pub bearer_token: String,
+   /// Chunk URLs from the homepage.
+   pub manifest:     ChunkManifest,
+   /// GraphQL operations declared in main.js.
+   pub graphql:      Catalog,
+}
+
+impl WebClientContext {
+   /// Fetches X.com, ondemand.js and main.js with the default
+   /// [`FetchOptions`].
+   #[cfg(feature = "fetch")]
+   pub fn fetch() -> Result<Self, Error> {
+      Self::fetch_with(&FetchOptions::default())
+   }
+
+   /// Like [`fetch`](Self::fetch), with custom options. main.js is fetched
+   /// from the URL in the homepage's `<script src>` tag.
+   #[cfg(feature = "fetch")]
+   pub fn fetch_with(options: &FetchOptions) -> Result<Self, Error> {
+      let home = options.get_home()?;
+      let (hash, ondemand) = options.get_ondemand(&home.body)?;
+
+      let manifest = ChunkManifest::parse(&home.body)?;
+      let main_url = manifest
+         .main_url()
+         .ok_or_else(|| Error::MissingKey("main.js script tag".into()))?;
+      let main_js = options.get(&main_url, "main.js")?;
+
+      Self::from_parts(
+         ClientTransaction::from_fetched(&home, hash, &ondemand, &options.registry)?,
+         manifest,
+         &main_js,
+      )
+   }
+
+   /// Creates a context from pre-fetched pages.
+   ///
+   /// Get the main.js URL with
+   /// [`ChunkManifest::main_url`](crate::manifest::ChunkManifest::main_url).
+   pub fn new(home_page_html: &str, ondemand_js: &str, main_js: &str) -> Result<Self, Error> {
+      Self::from_parts(
+         ClientTransaction::new(home_page_html, ondemand_js)?,
+         ChunkManifest::parse(home_page_html)?,
+         main_js,
+      )
+   }
+
+   fn from_parts(
+      transaction: ClientTransaction,
+      manifest: ChunkManifest,
+      main_js: &str,
+   ) -> Result<Self, Error> {
+      Ok(Self {
+         transaction,
+         bearer_token: parse::bearer_token(main_js)?,
+         manifest,
+         graphql: Catalog::parse(main_js),
+      })
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::testing::{
+      BEARER_TOKEN,
+      SyntheticPages,
+      main_js,
+   };
+
+   #[test]
+   fn from_pages() {
+      let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>())
+         .with_main_url("https://abs.twimg.com/responsive-web/client-web/main.0a1b2c3a.js");
+      let context =
+         WebClientContext::new(&pages.home_page_html(), &pages.ondemand_js(), &main_js()).unwrap();
+
+      assert_eq!(context.bearer_token, BEARER_TOKEN);
+      assert_eq!(context.transaction.key_bytes(), pages.key_bytes());
+      assert_eq!(
+         context.manifest.main_url().unwrap(),
+         "https://abs.twimg.com/responsive-web/client-web/main.0a1b2c3a.js"
+      );
+      assert_eq!(
+         context.graphql.path("UserByScreenName").unwrap(),
+         "/i/api/graphql/sYnThEtIc-QuErYiD/UserByScreenName"
+      );
+   }
+
+   #[test]
+   fn missing_bearer_token() {
+      let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>());
+      match WebClientContext::new(&pages.home_page_html(), &pages.ondemand_js(), "") {
+         Err(Error::MissingKey(_)) => {},
+         Err(err) => panic!("unexpected error: {err}"),
+         Ok(_) => panic!("built without a bearer token"),
+      }
+   }
+}
@@ -0,0 +1,124 @@
+//! Transaction ID decoding.
+
+use crate::{
+   error::Error,
+   timestamp::XTimestamp,
+   transaction::{
+      HASH_BYTES,
+      PROTOCOL_VERSION,
+   },
+   utils::base64_decode_unpadded,
+};
+
+/// Bytes following the key: 4 time bytes, the hash and the version byte.
+const TRAILER_BYTES: usize = 4 + HASH_BYTES + 1;
+
+/// Fields recovered from a transaction ID.
+///
+/// A transaction ID is a random byte followed by the verification key, the
+/// time, a truncated SHA-256 hash and a version byte, all XOR-ed with the
+/// random byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedTransactionId {
+   /// Byte every other byte was XOR-ed with.
+   pub random_byte: u8,
+   /// Verification key bytes.
+   pub key_bytes:   Vec<u8>,
+   /// Seconds since X's epoch (2023-05-01 07:00:00 UTC); see
+   /// [`timestamp`](Self::timestamp).
+   pub time:        u32,
+   /// First 16 bytes of the SHA-256 hash of the request.
+   pub hash:        [u8; HASH_BYTES],
+   /// Protocol version.
+   pub version:     u8,
+}
+
+impl DecodedTransactionId {
+   /// Decodes a transaction ID, with or without base64 padding.
+   pub fn decode(transaction_id: &str) -> Result<Self, Error> {
+      let bytes = base64_decode_unpadded(transaction_id)?;
+
+      let Some((&random_byte, rest)) = bytes.split_first() else {
+         return Err(Error::Parse("empty transaction ID".into()));
+      };
+
+      if rest.len() < TRAILER_BYTES {
+         return Err(Error::Parse(format!(
+            "transaction ID has {} bytes, need at least {}",
+            bytes.len(),
+            TRAILER_BYTES + 1
+         )));
+      }
+
+      let plain = rest
+         .iter()
+         .map(|&byte| byte ^ random_byte)
+         .collect::<Vec<_>>();
+      let (key_bytes, trailer) = plain.split_at(plain.len() - TRAILER_BYTES);
+      let (time_bytes, trailer) = trailer.split_at(4);
+      let (hash, version) = trailer.split_at(HASH_BYTES);
+
+      let mut time = [0; 4];
+      time.copy_from_slice(time_bytes);
+      let mut hash_bytes = [0; HASH_BYTES];
+      hash_bytes.copy_from_slice(hash);
+
+      Ok(Self {
+         random_byte,
+         key_bytes: key_bytes.to_vec(),
+         time: u32::from_le_bytes(time),
+         hash: hash_bytes,
+         version: version[0],
+      })
+   }
+
+   /// The time the ID was generated for.
+   #[must_use]
+   pub const fn timestamp(&self) -> XTimestamp {
+      XTimestamp::from_secs(self.time)
+   }
+
+   /// Returns `true` if the version byte matches the one this crate emits.
+   #[must_use]
+   pub const fn is_current_version(&self) -> bool {
+      self.version == PROTOCOL_VERSION
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::utils::base64_encode;
+
+   #[test]
+   fn decode_fields() {
+      let random_byte = 0x5A;
+      let mut plain = vec![1, 2, 3];
+      plain.extend_from_slice(&1234_u32.to_le_bytes());
+      plain.extend_from_slice(&[9; HASH_BYTES]);
+      plain.push(PROTOCOL_VERSION);
+
+      let mut bytes = vec![random_byte];
+      bytes.extend(plain.iter().map(|&byte| byte ^ random_byte));
+      let id = base64_encode(&bytes);
+
+      let decoded = DecodedTransactionId::decode(id.trim_end_matches('=')).unwrap();
+      assert_eq!(decoded.random_byte, random_byte);
+      assert_eq!(decoded.key_bytes, [1, 2, 3]);
+      assert_eq!(decoded.time, 1234);
+      assert_eq!(decoded.timestamp(), XTimestamp::from_secs(1234));
+      assert_eq!(decoded.hash, [9; HASH_BYTES]);
+      assert!(decoded.is_current_version());
+   }
+
+   #[test]
+   fn decode_too_short() {
+      DecodedTransactionId::decode("").unwrap_err();
+      DecodedTransactionId::decode("AAAA").unwrap_err();
+   }
+
+   #[test]
+   fn decode_invalid_base64() {
+      DecodedTransactionId::decode("not base64!").unwrap_err();
+   }
+}
@@ -0,0 +1,511 @@
+//! Finding the ondemand.s chunk URL on X's homepage.
+//!
+//! X's webpack runtime has shipped the chunk map in more than one shape, so
+//! several strategies run over the HTML and every URL they find is returned,
+//! best first:
+//!
+//! 1. [`AssetUrl`](Strategy::AssetUrl): a full URL in a `<link href>` or
+//!    `<script src>` attribute.
+//! 2. [`NamedChunkMap`](Strategy::NamedChunkMap): `"ondemand.s":"<hash>"`.
+//! 3. [`NumericChunkMap`](Strategy::NumericChunkMap): `123:"ondemand.s"` in the
+//!    name map, paired with `123:"<hash>"` in the hash map.
+//!
+//! Hashes found in chunk maps produce a URL with the usual `a.js` suffix and,
+//! at lower confidence, one ending in plain `.js`. Every hash is substituted
+//! into a URL template, by default [`ONDEMAND_URL_TEMPLATE`].
+
+use std::{
+   cmp::Reverse,
+   collections::BTreeMap,
+   fmt,
+   str::FromStr,
+};
+
+use crate::{
+   error::Error,
+   transaction::{
+      ONDEMAND_URL_TEMPLATE,
+      ondemand_url,
+   },
+};
+
+const CHUNK_NAME: &str = "ondemand.s";
+
+/// The chunk name as it appears, quoted, in a chunk map.
+const MARKERS: [&str; 2] = ["\"ondemand.s\"", "'ondemand.s'"];
+
+/// Confidence lost by guessing the alternate suffix.
+const ALTERNATE_SUFFIX_PENALTY: u8 = 50;
+
+/// Where a candidate URL was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Strategy {
+   /// A full URL in a `<link href>` or `<script src>` attribute.
+   AssetUrl,
+   /// A `"ondemand.s":"<hash>"` entry in a chunk map keyed by name.
+   NamedChunkMap,
+   /// A chunk ID mapped to `"ondemand.s"`, with the hash under the same ID
+   /// in a second map.
+   NumericChunkMap,
+}
+
+impl Strategy {
+   const fn confidence(self) -> u8 {
+      match self {
+         Self::AssetUrl => 100,
+         Self::NamedChunkMap => 90,
+         Self::NumericChunkMap => 80,
+      }
+   }
+
+   const fn name(self) -> &'static str {
+      match self {
+         Self::AssetUrl => "asset_url",
+         Self::NamedChunkMap => "named_chunk_map",
+         Self::NumericChunkMap => "numeric_chunk_map",
+      }
+   }
+}
+
+/// Formats as `asset_url`, `named_chunk_map` or `numeric_chunk_map`.
+impl fmt::Display for Strategy {
+   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      f.write_str(self.name())
+   }
+}
+
+impl FromStr for Strategy {
+   type Err = Error;
+
+   fn from_str(s: &str) -> Result<Self, Self::Err> {
+      [Self::AssetUrl, Self::NamedChunkMap, Self::NumericChunkMap]
+         .into_iter()
+         .find(|strategy| strategy.name() == s)
+         .ok_or_else(|| Error::Parse(format!("unknown strategy `{s}`")))
+   }
+}
+
+/// A possible URL of the ondemand.s chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OndemandCandidate {
+   /// Absolute URL of the chunk.
+   pub url:        String,
+   /// Chunk hash, as substituted into the URL template, if the URL follows
+   /// the usual `ondemand.s.<hash>a.js` form.
+   pub hash:       Option<String>,
+   /// How the candidate was found.
+   pub strategy:   Strategy,
+   /// Higher is more likely, up to 100.
+   pub confidence: u8,
+}
+
+/// Returns every ondemand.s URL found in homepage HTML, most likely first and
+/// without duplicates.
+#[must_use]
+pub fn ondemand_candidates(home_page_html: &str) -> Vec<OndemandCandidate> {
+   ondemand_candidates_with(home_page_html, ONDEMAND_URL_TEMPLATE)
+}
+
+/// Like [`ondemand_candidates`], building the URL of every hash found from
+/// `template`, where `{hash}` stands in for the hash.
+///
+/// Asset URLs with a hash are rebuilt from `template` too; those without
+/// one are returned as found. Alternate-suffix URLs are only guessed when
+/// the template ends its hash with `a.`, as X's does.
+#[must_use]
+#[expect(
+   clippy::literal_string_with_formatting_args,
+   reason = "`{hash}` is a URL template placeholder"
+)]
+pub fn ondemand_candidates_with(home_page_html: &str, template: &str) -> Vec<OndemandCandidate> {
+   let alternate = template
+      .contains("{hash}a.")
+      .then(|| template.replacen("{hash}a.", "{hash}.", 1));
+
+   let mut candidates = asset_urls(home_page_html);
+   for candidate in &mut candidates {
+      if let Some(ref hash) = candidate.hash {
+         candidate.url = ondemand_url(template, hash);
+      }
+   }
+
+   let hashes = named_hashes(home_page_html)
+      .into_iter()
+      .map(|hash| (Strategy::NamedChunkMap, hash))
+      .chain(
+         numeric_hashes(home_page_html)
+            .into_iter()
+            .map(|hash| (Strategy::NumericChunkMap, hash)),
+      );
+   for (strategy, hash) in hashes {
+      if let Some(ref alternate) = alternate {
+         candidates.push(OndemandCandidate {
+            url: ondemand_url(alternate, &hash),
+            hash: None,
+            strategy,
+            confidence: strategy.confidence() - ALTERNATE_SUFFIX_PENALTY,
+         });
+      }
+      candidates.push(OndemandCandidate {
+         url: ondemand_url(template, &hash),
+         hash: Some(hash),
+         strategy,
+         confidence: strategy.confidence(),
+      });
+   }
+
+   candidates.sort_by_key(|candidate| Reverse(candidate.confidence));
+   let mut unique = Vec::<OndemandCandidate>::with_capacity(candidates.len());
+   for candidate in candidates {
+      if !unique.iter().any(|seen| seen.url == candidate.url) {
+         unique.push(candidate);
+      }
+   }
+   unique
+}
+
+/// Returns the hash of the most likely candidate that has one.
+pub fn ondemand_hash(home_page_html: &str) -> Result<String, Error> {
+   ondemand_candidates(home_page_html)
+      .into_iter()
+      .find_map(|candidate| candidate.hash)
+      .ok_or_else(|| missing(home_page_html))
+}
+
+/// Error for a homepage without any candidate, with a guess at why.
+pub(crate) fn missing(home_page_html: &str) -> Error {
+   let hint = if home_page_html.contains("login") || home_page_html.contains("LoginForm") {
+      " (received login page - may need cookies)"
+   } else if home_page_html.len() < 10000 {
+      " (response too small - may be rate limited or blocked)"
+   } else {
+      " (X may have changed their page structure)"
+   };
+   Error::MissingKey(format!("ondemand file hash{hint}"))
+}
+
+/// Whether `value` looks like a chunk hash: non-empty ASCII letters and
+/// digits.
+pub(crate) fn is_hash(value: &str) -> bool {
+   !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_alphanumeric())
+}
+
+/// Splits a leading `"`- or `'`-quoted string off `text`.
+pub(crate) fn quoted(text: &str) -> Option<(&str, &str)> {
+   let quote = text
+      .chars()
+      .next()
+      .filter(|&chr| chr == '"' || chr == '\'')?;
+   let rest = &text[1..];
+   let end = rest.find(quote)?;
+   Some((&rest[..end], &rest[end + 1..]))
+}
+
+/// Hashes from `"ondemand.s":"<hash>"` entries.
+fn named_hashes(html: &str) -> Vec<String> {
+   let mut hashes = Vec::new();
+
+   for marker in MARKERS {
+      for (pos, _) in html.match_indices(marker) {
+         let hash = html[pos + marker.len()..]
+            .trim_start()
+            .strip_prefix(':')
+            .map(str::trim_start)
+            .and_then(quoted)
+            .map(|(hash, _)| hash);
+
+         if let Some(hash) = hash
+            && is_hash(hash)
+            && !hashes.iter().any(|seen| seen == hash)
+         {
+            hashes.push(hash.to_owned());
+         }
+      }
+   }
+
+   hashes
+}
+
+/// Hashes paired with the chunk ID of `<id>:"ondemand.s"` entries.
+fn numeric_hashes(html: &str) -> Vec<String> {
+   let mut hashes = Vec::new();
+
+   for marker in MARKERS {
+      for (pos, _) in html.match_indices(marker) {
+         let Some(id) = chunk_id_before(&html[..pos]) else {
+            continue;
+         };
+
+         for hash in values_for_id(html, id) {
+            if is_hash(hash) && !hashes.iter().any(|seen| seen == hash) {
+               hashes.push(hash.to_owned());
+            }
+         }
+      }
+   }
+
+   hashes
+}
+
+/// The numeric key of the map entry whose value starts where `before` ends.
+fn chunk_id_before(before: &str) -> Option<&str> {
+   let key = before.trim_end().strip_suffix(':')?.trim_end();
+   let key = key.strip_suffix(['"', '\'']).unwrap_or(key);
+
+   let digits = key.bytes().rev().take_while(u8::is_ascii_digit).count();
+   let id = &key[key.len() - digits..];
+   (!id.is_empty()).then_some(id)
+}
+
+/// Values of every `<id>:"<value>"` map entry other than the chunk name.
+fn values_for_id<'a>(html: &'a str, id: &str) -> Vec<&'a str> {
+   let mut values = Vec::new();
+
+   for (pos, _) in html.match_indices(id) {
+      let before = html[..pos].chars().next_back();
+      if before.is_some_and(|chr| chr.is_ascii_alphanumeric() || chr == '.') {
+         continue;
+      }
+
+      let after = &html[pos + id.len()..];
+      let after = after.strip_prefix(['"', '\'']).unwrap_or(after);
+      let value = after
+         .trim_start()
+         .strip_prefix(':')
+         .map(str::trim_start)
+         .and_then(quoted)
+         .map(|(value, _)| value);
+
+      if let Some(value) = value
+         && value != CHUNK_NAME
+      {
+         values.push(value);
+      }
+   }
+
+   values
+}
+
+/// Reads every chunk map around an `ondemand.s` entry into name → hash,
+/// for all chunks rather than just ondemand.s.
+pub(crate) fn chunk_map(html: &str) -> BTreeMap<String, String> {
+   let mut chunks = BTreeMap::new();
+
+   for marker in MARKERS {
+      for (pos, _) in html.match_indices(marker) {
+         let Some((end, entries)) = object_around(html, pos) else {
+            continue;
+         };
+
+         if entries.iter().any(|&(key, _)| key == CHUNK_NAME) {
+            for (name, hash) in entries {
+               if is_hash(hash) {
+                  chunks.insert(name.to_owned(), hash.to_owned());
+               }
+            }
+            continue;
+         }
+
+         // Numeric map: `{id:"name"}` followed by `{id:"hash"}`.
+         let Some(next) = html[end..].find('{').map(|offset| end + offset + 1) else {
+            continue;
+         };
+         let Some((_, hashes)) = object_around(html, next) else {
+            continue;
+         };
+         for &(id, name) in &entries {
+            let hash = hashes.iter().find(|&&(key, _)| key == id);
+            if let Some(&(_, hash)) = hash
+               && is_hash(hash)
+            {
+               chunks.insert(name.to_owned(), hash.to_owned());
+            }
+         }
+      }
+   }
+
+   chunks
+}
+
+/// Entries of the object literal containing `pos`, and the index just past
+/// its closing brace.
+fn object_around(html: &str, pos: usize) -> Option<(usize, Vec<(&str, &str)>)> {
+   let start = html[..pos].rfind('{')? + 1;
+   let end = pos + html[pos..].find('}')?;
+
+   let entries = html[start..end]
+      .split(',')
+      .filter_map(|entry| {
+         let (key, value) = entry.split_once(':')?;
+         let key = key.trim();
+         let key = unquote(key).unwrap_or(key);
+         Some((key, unquote(value.trim())?))
+      })
+      .collect();
+
+   Some((end + 1, entries))
+}
+
+/// `text` without the quotes around it, if it is a single quoted string.
+fn unquote(text: &str) -> Option<&str> {
+   quoted(text)
+      .filter(|&(_, rest)| rest.is_empty())
+      .map(|(value, _)| value)
+}
+
+/// Full ondemand.s URLs in `href` and `src` attributes.
+fn asset_urls(html: &str) -> Vec<OndemandCandidate> {
+   let mut candidates = Vec::new();
+
+   for attribute in ["href=", "src="] {
+      for (pos, _) in html.match_indices(attribute) {
+         let Some((value, _)) = quoted(&html[pos + attribute.len()..]) else {
+            continue;
+         };
+
+         let url = if let Some(rest) = value.strip_prefix("//") {
+            format!("https://{rest}")
+         } else if value.starts_with("https://") || value.starts_with("http://") {
+            value.to_owned()
+         } else {
+            continue;
+         };
+
+         let file = url.rsplit('/').next().unwrap_or_default();
+         let file = file.split(['?', '#']).next().unwrap_or_default();
+         let Some(stem) = file
+            .strip_prefix("ondemand.s.")
+            .and_then(|rest| rest.strip_suffix(".js"))
+         else {
+            continue;
+         };
+
+         candidates.push(OndemandCandidate {
+            hash: stem
+               .strip_suffix('a')
+               .filter(|hash| is_hash(hash))
+               .map(ToOwned::to_owned),
+            url,
+            strategy: Strategy::AssetUrl,
+            confidence: Strategy::AssetUrl.confidence(),
+         });
+      }
+   }
+
+   candidates
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn urls(html: &str) -> Vec<String> {
+      ondemand_candidates(html)
+         .into_iter()
+         .map(|candidate| candidate.url)
+         .collect()
+   }
+
+   #[test]
+   fn named_chunk_map() {
+      let candidates = ondemand_candidates(r#"{"vendor":"111","ondemand.s":"abc123"}"#);
+      assert_eq!(candidates.len(), 2);
+      assert_eq!(
+         candidates[0].url,
+         "https://abs.twimg.com/responsive-web/client-web/ondemand.s.abc123a.js"
+      );
+      assert_eq!(candidates[0].hash.as_deref(), Some("abc123"));
+      assert_eq!(candidates[0].strategy, Strategy::NamedChunkMap);
+      assert_eq!(
+         candidates[1].url,
+         "https://abs.twimg.com/responsive-web/client-web/ondemand.s.abc123.js"
+      );
+      assert!(candidates[1].confidence < candidates[0].confidence);
+   }
+
+   #[test]
+   fn numeric_chunk_map() {
+      let html =
+         r#"e=>e+"."+{20:"vendor",8712:"ondemand.s"}[e]+"."+{20:"f00",8712:"beef42"}[e]+"a.js""#;
+      let candidates = ondemand_candidates(html);
+      assert_eq!(candidates[0].hash.as_deref(), Some("beef42"));
+      assert_eq!(candidates[0].strategy, Strategy::NumericChunkMap);
+      assert_eq!(ondemand_hash(html).unwrap(), "beef42");
+   }
+
+   #[test]
+   fn numeric_chunk_map_quoted_ids() {
+      let html = r#"{"12":"ondemand.s","112":"other"}{"112":"nope","12":"cafe01"}"#;
+      assert_eq!(ondemand_hash(html).unwrap(), "cafe01");
+   }
+
+   #[test]
+   fn asset_urls_rank_first() {
+      let html = r#"<link rel="preload" href="https://abs.twimg.com/responsive-web/client-web/ondemand.s.d00d99a.js" as="script"><script>{"ondemand.s":"abc123"}</script><script src="//cdn.example/ondemand.s.77.js"></script>"#;
+      assert_eq!(urls(html), [
+         "https://abs.twimg.com/responsive-web/client-web/ondemand.s.d00d99a.js",
+         "https://cdn.example/ondemand.s.77.js",
+         "https://abs.twimg.com/responsive-web/client-web/ondemand.s.abc123a.js",
+         "https://abs.twimg.com/responsive-web/client-web/ondemand.s.abc123.js",
+      ]);
+
+      let candidates = ondemand_candidates(html);
+      assert_eq!(candidates[0].hash.as_deref(), Some("d00d99"));
+      assert_eq!(candidates[1].hash, None);
+      assert_eq!(ondemand_hash(html).unwrap(), "d00d99");
+   }
+
+   #[test]
+   fn custom_template() {
+      let html = r#"<link href="https://abs.twimg.com/responsive-web/client-web/ondemand.s.d00d99a.js">{"ondemand.s":"abc123"}"#;
+      assert_eq!(urls(html).len(), 3);
+      assert_eq!(
+         ondemand_candidates_with(html, "http://mirror.test/{hash}a.js")
+            .into_iter()
+            .map(|candidate| candidate.url)
+            .collect::<Vec<_>>(),
+         [
+            "http://mirror.test/d00d99a.js",
+            "http://mirror.test/abc123a.js",
+            "http://mirror.test/abc123.js",
+         ]
+      );
+      assert_eq!(
+         ondemand_candidates_with(html, "http://mirror.test/{hash}.js").len(),
+         2
+      );
+   }
+
+   #[test]
+   fn duplicates_are_dropped() {
+      let html = r#"<link href="https://abs.twimg.com/responsive-web/client-web/ondemand.s.abc123a.js">{"ondemand.s":"abc123"}"#;
+      let candidates = ondemand_candidates(html);
+      assert_eq!(candidates.len(), 2);
+      assert_eq!(candidates[0].strategy, Strategy::AssetUrl);
+   }
+
+   #[test]
+   fn strategy_names() {
+      for strategy in [
+         Strategy::AssetUrl,
+         Strategy::NamedChunkMap,
+         Strategy::NumericChunkMap,
+      ] {
+         assert_eq!(strategy.to_string().parse::<Strategy>().unwrap(), strategy);
+      }
+      "guess".parse::<Strategy>().unwrap_err();
+   }
+
+   #[test]
+   fn hashes_are_ascii() {
+      assert!(is_hash("5a1b2c3"));
+      assert!(!is_hash("5a1b2c3é"));
+      ondemand_hash(r#"{"ondemand.s":"ａｂｃ"}"#).unwrap_err();
+   }
+
+   #[test]
+   fn nothing_found() {
+      assert!(ondemand_candidates("no ondemand here").is_empty());
+      ondemand_hash("no ondemand here").unwrap_err();
+   }
+}
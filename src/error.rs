@@ -3,6 +3,7 @@
 use std::{
    error::Error as StdError,
    fmt,
+   time::Duration,
 };
 
 #[derive(Debug)]
@@ -15,12 +16,19 @@ pub enum Error {
    MissingKey(String),
    /// Base64 decoding failed.
    Base64(data_encoding::DecodeError),
+   /// Time is before X's epoch (2023-05-01 07:00:00 UTC), by this much.
+   BeforeEpoch(Duration),
+   /// Time is this many seconds after X's epoch, past what an ID can hold.
+   TimestampOverflow(u64),
    /// HTTP request failed.
    #[cfg(feature = "fetch")]
    Http(minreq::Error),
    /// HTTP response returned non-200 status.
    #[cfg(feature = "fetch")]
    HttpStatus(i32, &'static str),
+   /// HTTP response body exceeded the configured size limit.
+   #[cfg(feature = "fetch")]
+   BodyTooLarge(usize, &'static str),
 }
 
 impl fmt::Display for Error {
@@ -32,10 +40,21 @@ impl fmt::Display for Error {
          Self::Parse(ref msg) => write!(f, "parse error: {msg}"),
          Self::MissingKey(ref key) => write!(f, "missing required key: {key}"),
          Self::Base64(ref err) => write!(f, "base64 decode error: {err}"),
+         Self::BeforeEpoch(early) => {
+            write!(f, "time is {}s before X's epoch", early.as_secs())
+         },
+         Self::TimestampOverflow(secs) => {
+            write!(
+               f,
+               "time is {secs}s after X's epoch, past 2159-06-07 13:28:15 UTC"
+            )
+         },
          #[cfg(feature = "fetch")]
          Self::Http(ref err) => write!(f, "HTTP error: {err}"),
          #[cfg(feature = "fetch")]
          Self::HttpStatus(code, url) => write!(f, "{url} returned HTTP {code}"),
+         #[cfg(feature = "fetch")]
+         Self::BodyTooLarge(limit, url) => write!(f, "{url} response exceeds {limit} bytes"),
       }
    }
 }
@@ -44,11 +63,15 @@ impl StdError for Error {
    fn source(&self) -> Option<&(dyn StdError + 'static)> {
       match *self {
          Self::Base64(ref err) => Some(err),
-         Self::MismatchedArguments | Self::Parse(_) | Self::MissingKey(_) => None,
+         Self::MismatchedArguments
+         | Self::Parse(_)
+         | Self::MissingKey(_)
+         | Self::BeforeEpoch(_)
+         | Self::TimestampOverflow(_) => None,
          #[cfg(feature = "fetch")]
          Self::Http(ref err) => Some(err),
          #[cfg(feature = "fetch")]
-         Self::HttpStatus(..) => None,
+         Self::HttpStatus(..) | Self::BodyTooLarge(..) => None,
       }
    }
 }
@@ -0,0 +1,247 @@
+//! GraphQL operations declared in X's JavaScript bundles.
+//!
+//! Every operation X's web client can send is compiled into its bundles as an
+//! object like
+//!
+//! ```js
+//! {queryId:"G3KGOASz96M-Qu0nwmGXNg",operationName:"UserByScreenName",operationType:"query",
+//!  metadata:{featureSwitches:["hidden_profile_subscriptions_enabled",...],fieldToggles:[...]}}
+//! ```
+//!
+//! The query ID changes with each deployment, so paths for
+//! [`generate_transaction_id`](crate::ClientTransaction::generate_transaction_id)
+//! are best looked up by operation name from a freshly built [`Catalog`].
+
+use std::collections::BTreeMap;
+
+/// A GraphQL operation found in a bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operation {
+   /// Per-deployment ID, the second to last path segment.
+   pub query_id:         String,
+   /// Name, e.g. `UserByScreenName`.
+   pub operation_name:   String,
+   /// `query` or `mutation`; empty if the bundle didn't say.
+   pub operation_type:   String,
+   /// Feature switches the request's `features` parameter must list.
+   pub feature_switches: Vec<String>,
+   /// Field toggles the request's `fieldToggles` parameter may list.
+   pub field_toggles:    Vec<String>,
+}
+
+impl Operation {
+   /// API path of the operation, e.g.
+   /// `/i/api/graphql/G3KGOASz96M-Qu0nwmGXNg/UserByScreenName`.
+   #[must_use]
+   pub fn path(&self) -> String {
+      format!("/i/api/graphql/{}/{}", self.query_id, self.operation_name)
+   }
+
+   /// Parses the operation object around a `queryId` key at `pos`.
+   fn parse_at(js: &str, pos: usize) -> Option<Self> {
+      let start = js[..pos].rfind('{')?;
+      let object = balanced_object(&js[start..])?;
+
+      Some(Self {
+         query_id:         string_field(object, "queryId")?.to_owned(),
+         operation_name:   string_field(object, "operationName")?.to_owned(),
+         operation_type:   string_field(object, "operationType")
+            .unwrap_or_default()
+            .to_owned(),
+         feature_switches: list_field(object, "featureSwitches"),
+         field_toggles:    list_field(object, "fieldToggles"),
+      })
+   }
+}
+
+/// Operations by name, collected from one or more bundles.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Catalog {
+   operations: BTreeMap<String, Operation>,
+}
+
+impl Catalog {
+   /// Collects the operations declared in `js`.
+   #[must_use]
+   pub fn parse(js: &str) -> Self {
+      let mut catalog = Self::default();
+      catalog.add_bundle(js);
+      catalog
+   }
+
+   /// Adds the operations declared in another bundle, e.g. a lazily loaded
+   /// chunk. Later bundles win for operations declared twice.
+   pub fn add_bundle(&mut self, js: &str) {
+      for key in ["queryId:", "\"queryId\":"] {
+         for (pos, _) in js.match_indices(key) {
+            if let Some(operation) = Operation::parse_at(js, pos) {
+               self
+                  .operations
+                  .insert(operation.operation_name.clone(), operation);
+            }
+         }
+      }
+   }
+
+   /// The operation named `operation_name`.
+   #[must_use]
+   pub fn get(&self, operation_name: &str) -> Option<&Operation> {
+      self.operations.get(operation_name)
+   }
+
+   /// API path of the operation named `operation_name`, ready for
+   /// [`generate_transaction_id`](crate::ClientTransaction::generate_transaction_id).
+   #[must_use]
+   pub fn path(&self, operation_name: &str) -> Option<String> {
+      self.get(operation_name).map(Operation::path)
+   }
+
+   /// All operations, sorted by name.
+   pub fn operations(&self) -> impl Iterator<Item = &Operation> {
+      self.operations.values()
+   }
+
+   #[must_use]
+   pub fn len(&self) -> usize {
+      self.operations.len()
+   }
+
+   #[must_use]
+   pub fn is_empty(&self) -> bool {
+      self.operations.is_empty()
+   }
+}
+
+/// The object literal at the start of `js`, up to its matching brace.
+/// Braces inside string literals are skipped.
+fn balanced_object(js: &str) -> Option<&str> {
+   let mut depth = 0_usize;
+   let mut quote = None;
+   let mut escaped = false;
+
+   for (index, chr) in js.char_indices() {
+      if let Some(open) = quote {
+         if escaped {
+            escaped = false;
+         } else if chr == '\\' {
+            escaped = true;
+         } else if chr == open {
+            quote = None;
+         }
+         continue;
+      }
+
+      match chr {
+         '"' | '\'' => quote = Some(chr),
+         '{' => depth += 1,
+         '}' => {
+            depth = depth.checked_sub(1)?;
+            if depth == 0 {
+               return Some(&js[..=index]);
+            }
+         },
+         _ => {},
+      }
+   }
+
+   None
+}
+
+/// Position just past `name:` or `"name":` in `object`.
+fn field_value<'a>(object: &'a str, name: &str) -> Option<&'a str> {
+   [format!("{name}:"), format!("\"{name}\":")]
+      .iter()
+      .find_map(|key| {
+         object
+            .find(key.as_str())
+            .map(|pos| &object[pos + key.len()..])
+      })
+      .map(str::trim_start)
+}
+
+/// Quoted string value of `name`.
+fn string_field<'a>(object: &'a str, name: &str) -> Option<&'a str> {
+   let value = field_value(object, name)?;
+   let quote = value
+      .chars()
+      .next()
+      .filter(|&chr| chr == '"' || chr == '\'')?;
+   let rest = &value[1..];
+   rest.find(quote).map(|end| &rest[..end])
+}
+
+/// Quoted strings in the array value of `name`.
+fn list_field(object: &str, name: &str) -> Vec<String> {
+   let Some(list) = field_value(object, name)
+      .and_then(|value| value.strip_prefix('['))
+      .and_then(|value| value.find(']').map(|end| &value[..end]))
+   else {
+      return Vec::new();
+   };
+
+   list
+      .split(',')
+      .map(|item| item.trim().trim_matches(['"', '\'']))
+      .filter(|item| !item.is_empty())
+      .map(ToOwned::to_owned)
+      .collect()
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   const BUNDLE: &str = r#"e.exports={queryId:"G3KGOASz96M-Qu0nwmGXNg",operationName:"UserByScreenName",operationType:"query",metadata:{featureSwitches:["hidden_profile_subscriptions_enabled","responsive_web_graphql_exclude_directive_enabled"],fieldToggles:["withAuxiliaryUserLabels"]}}},12345:e=>{e.exports={queryId:"a1b2c3",operationName:"CreateTweet",operationType:"mutation",metadata:{featureSwitches:[],fieldToggles:[]}}}"#;
+
+   #[test]
+   fn operations() {
+      let catalog = Catalog::parse(BUNDLE);
+      assert_eq!(catalog.len(), 2);
+
+      let user = catalog.get("UserByScreenName").unwrap();
+      assert_eq!(user.query_id, "G3KGOASz96M-Qu0nwmGXNg");
+      assert_eq!(user.operation_type, "query");
+      assert_eq!(user.feature_switches, [
+         "hidden_profile_subscriptions_enabled",
+         "responsive_web_graphql_exclude_directive_enabled"
+      ]);
+      assert_eq!(user.field_toggles, ["withAuxiliaryUserLabels"]);
+
+      let tweet = catalog.get("CreateTweet").unwrap();
+      assert_eq!(tweet.operation_type, "mutation");
+      assert!(tweet.feature_switches.is_empty());
+   }
+
+   #[test]
+   fn paths() {
+      let catalog = Catalog::parse(BUNDLE);
+      assert_eq!(
+         catalog.path("UserByScreenName").unwrap(),
+         "/i/api/graphql/G3KGOASz96M-Qu0nwmGXNg/UserByScreenName"
+      );
+      assert_eq!(catalog.path("Missing"), None);
+   }
+
+   #[test]
+   fn later_bundles_win() {
+      let mut catalog = Catalog::parse(BUNDLE);
+      catalog.add_bundle(
+         r#"{"queryId":"zzz999","operationName":"CreateTweet","operationType":"mutation"}"#,
+      );
+      assert_eq!(catalog.len(), 2);
+      assert_eq!(catalog.get("CreateTweet").unwrap().query_id, "zzz999");
+   }
+
+   #[test]
+   fn braces_in_strings() {
+      let catalog = Catalog::parse(
+         r#"{queryId:"q1",operationName:"Odd",metadata:{note:"}{"},operationType:"query"}"#,
+      );
+      assert_eq!(catalog.get("Odd").unwrap().operation_type, "query");
+   }
+
+   #[test]
+   fn empty_bundle() {
+      assert!(Catalog::parse("function(){return 1}").is_empty());
+   }
+}
@@ -0,0 +1,312 @@
+//! Guest tokens for unauthenticated requests.
+//!
+//! Logged-out requests carry an `x-guest-token` header. Tokens come from
+//! `POST /1.1/guest/activate.json`, which X checks the transaction ID of like
+//! any other request. [`TokenCache`] activates one when needed and reuses it
+//! until it expires.
+//!
+//! ```ignore
+//! let guest = TokenCache::new(bearer_token);
+//! let token = guest.token(&client, &FetchOptions::default())?;
+//! ```
+
+use std::{
+   sync::{
+      Mutex,
+      PoisonError,
+   },
+   time::{
+      Duration,
+      SystemTime,
+   },
+};
+
+#[cfg(feature = "fetch")] use crate::fetch::FetchOptions;
+use crate::{
+   error::Error,
+   transaction::ClientTransaction,
+};
+
+/// Guest token activation endpoint.
+pub const ACTIVATE_URL: &str = "https://api.x.com/1.1/guest/activate.json";
+
+/// A guest token and when it stops being used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+   token:      String,
+   expires_at: SystemTime,
+}
+
+impl Token {
+   #[must_use]
+   pub const fn new(token: String, expires_at: SystemTime) -> Self {
+      Self { token, expires_at }
+   }
+
+   /// Value for the `x-guest-token` header.
+   #[must_use]
+   pub fn token(&self) -> &str {
+      &self.token
+   }
+
+   #[must_use]
+   pub const fn expires_at(&self) -> SystemTime {
+      self.expires_at
+   }
+
+   #[must_use]
+   pub fn is_expired(&self) -> bool {
+      SystemTime::now() >= self.expires_at
+   }
+}
+
+/// An activation request for a caller-supplied HTTP client to send.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActivationRequest {
+   /// URL to `POST` to, with an empty body.
+   pub url:     String,
+   /// Headers to send, including `authorization` and
+   /// `x-client-transaction-id`.
+   pub headers: Vec<(String, String)>,
+}
+
+/// Activates guest tokens and caches the current one.
+///
+/// Safe to share between threads; concurrent callers may both activate a
+/// token when the cached one expires, and the later one is kept.
+pub struct TokenCache {
+   bearer_token: String,
+   activate_url: String,
+   lifetime:     Duration,
+   current:      Mutex<Option<Token>>,
+}
+
+impl TokenCache {
+   /// How long a token is used before activating another. X doesn't say;
+   /// tokens have lasted around three hours. One rejected earlier can be
+   /// replaced with [`refresh_with`](Self::refresh_with).
+   pub const DEFAULT_LIFETIME: Duration = Duration::from_hours(3);
+
+   /// Activates tokens at [`ACTIVATE_URL`] with the web client's bearer
+   /// token, e.g. from
+   /// [`WebClientContext`](crate::WebClientContext::bearer_token).
+   pub fn new(bearer_token: impl Into<String>) -> Self {
+      Self {
+         bearer_token: bearer_token.into(),
+         activate_url: ACTIVATE_URL.to_owned(),
+         lifetime:     Self::DEFAULT_LIFETIME,
+         current:      Mutex::new(None),
+      }
+   }
+
+   /// Activates tokens at `url` instead, e.g. a test server.
+   #[must_use]
+   pub fn with_activate_url(mut self, url: impl Into<String>) -> Self {
+      self.activate_url = url.into();
+      self
+   }
+
+   /// Uses each token for `lifetime` before activating another.
+   #[must_use]
+   pub const fn with_lifetime(mut self, lifetime: Duration) -> Self {
+      self.lifetime = lifetime;
+      self
+   }
+
+   /// The cached token, if it hasn't expired.
+   #[must_use]
+   pub fn cached(&self) -> Option<Token> {
+      self
+         .current
+         .lock()
+         .unwrap_or_else(PoisonError::into_inner)
+         .clone()
+         .filter(|token| !token.is_expired())
+   }
+
+   /// Drops the cached token, e.g. after X rejected it, so the next call
+   /// activates a new one.
+   pub fn invalidate(&self) {
+      *self.current.lock().unwrap_or_else(PoisonError::into_inner) = None;
+   }
+
+   /// The request that activates a token, with a transaction ID from
+   /// `client`.
+   #[must_use]
+   pub fn activation_request(&self, client: &ClientTransaction) -> ActivationRequest {
+      let id = client.generate_transaction_id("POST", url_path(&self.activate_url));
+
+      ActivationRequest {
+         url:     self.activate_url.clone(),
+         headers: vec![
+            (
+               "authorization".to_owned(),
+               format!("Bearer {}", self.bearer_token),
+            ),
+            ("x-client-transaction-id".to_owned(), id),
+         ],
+      }
+   }
+
+   /// Returns the cached token, or activates one by passing the
+   /// [`activation_request`](Self::activation_request) to `send`, which
+   /// returns the response body.
+   pub fn token_with(
+      &self,
+      client: &ClientTransaction,
+      send: impl FnOnce(&ActivationRequest) -> Result<String, Error>,
+   ) -> Result<Token, Error> {
+      if let Some(token) = self.cached() {
+         return Ok(token);
+      }
+      self.refresh_with(client, send)
+   }
+
+   /// Activates a new token through `send`, even if the cached one is still
+   /// valid.
+   pub fn refresh_with(
+      &self,
+      client: &ClientTransaction,
+      send: impl FnOnce(&ActivationRequest) -> Result<String, Error>,
+   ) -> Result<Token, Error> {
+      let body = send(&self.activation_request(client))?;
+      let token = Token::new(parse_guest_token(&body)?, SystemTime::now() + self.lifetime);
+
+      *self.current.lock().unwrap_or_else(PoisonError::into_inner) = Some(token.clone());
+      Ok(token)
+   }
+
+   /// Like [`token_with`](Self::token_with), sending the request with the
+   /// built-in client.
+   #[cfg(feature = "fetch")]
+   pub fn token(&self, client: &ClientTransaction, options: &FetchOptions) -> Result<Token, Error> {
+      self.token_with(client, |request| post(request, options))
+   }
+
+   /// Like [`refresh_with`](Self::refresh_with), sending the request with the
+   /// built-in client.
+   #[cfg(feature = "fetch")]
+   pub fn refresh(
+      &self,
+      client: &ClientTransaction,
+      options: &FetchOptions,
+   ) -> Result<Token, Error> {
+      self.refresh_with(client, |request| post(request, options))
+   }
+}
+
+#[cfg(feature = "fetch")]
+fn post(request: &ActivationRequest, options: &FetchOptions) -> Result<String, Error> {
+   let headers = request
+      .headers
+      .iter()
+      .map(|&(ref name, ref value)| (name.as_str(), value.as_str()))
+      .collect::<Vec<_>>();
+   options.post(&request.url, "guest/activate.json", &headers)
+}
+
+/// Path of `url`, which the transaction ID is generated for.
+fn url_path(url: &str) -> &str {
+   let after_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+   after_scheme
+      .find('/')
+      .map_or("/", |start| &after_scheme[start..])
+}
+
+/// Reads `guest_token` from the activation response.
+fn parse_guest_token(body: &str) -> Result<String, Error> {
+   let missing = || Error::MissingKey("guest_token in activation response".into());
+
+   let pos = body.find("\"guest_token\"").ok_or_else(missing)?;
+   let value = body[pos + "\"guest_token\"".len()..]
+      .trim_start()
+      .strip_prefix(':')
+      .ok_or_else(missing)?
+      .trim_start();
+
+   // Usually a string, but accept a bare number too.
+   let token = value
+      .strip_prefix('"')
+      .and_then(|rest| rest.find('"').map(|end| &rest[..end]))
+      .unwrap_or_else(|| {
+         let end = value
+            .find(|chr: char| !chr.is_ascii_digit())
+            .unwrap_or(value.len());
+         &value[..end]
+      });
+
+   if token.is_empty() || !token.chars().all(|chr| chr.is_ascii_digit()) {
+      return Err(Error::Parse(format!("invalid guest token `{token}`")));
+   }
+   Ok(token.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::testing::SyntheticPages;
+
+   fn client() -> ClientTransaction {
+      let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>());
+      ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap()
+   }
+
+   #[test]
+   fn guest_token_formats() {
+      assert_eq!(
+         parse_guest_token(r#"{"guest_token":"1712345678901234567"}"#).unwrap(),
+         "1712345678901234567"
+      );
+      assert_eq!(
+         parse_guest_token(r#"{ "guest_token" : 42 }"#).unwrap(),
+         "42"
+      );
+      parse_guest_token(r#"{"errors":[]}"#).unwrap_err();
+      parse_guest_token(r#"{"guest_token":"abc"}"#).unwrap_err();
+   }
+
+   #[test]
+   fn url_paths() {
+      assert_eq!(url_path(ACTIVATE_URL), "/1.1/guest/activate.json");
+      assert_eq!(url_path("http://127.0.0.1:80"), "/");
+   }
+
+   #[test]
+   fn caches_until_invalidated() {
+      let guest = TokenCache::new("bearer");
+      let client = client();
+      let mut requests = Vec::new();
+      let mut send = |request: &ActivationRequest| {
+         requests.push(request.clone());
+         Ok(format!(r#"{{"guest_token":"{}"}}"#, requests.len()))
+      };
+
+      assert_eq!(guest.token_with(&client, &mut send).unwrap().token(), "1");
+      assert_eq!(guest.token_with(&client, &mut send).unwrap().token(), "1");
+      guest.invalidate();
+      assert_eq!(guest.token_with(&client, &mut send).unwrap().token(), "2");
+
+      assert_eq!(requests.len(), 2);
+      assert_eq!(requests[0].url, ACTIVATE_URL);
+      assert_eq!(
+         requests[0].headers[0],
+         ("authorization".to_owned(), "Bearer bearer".to_owned())
+      );
+   }
+
+   #[test]
+   fn expired_tokens_are_replaced() {
+      let guest = TokenCache::new("bearer").with_lifetime(Duration::ZERO);
+      let client = client();
+      let mut count = 0;
+      let mut send = |_: &ActivationRequest| {
+         count += 1;
+         Ok(format!(r#"{{"guest_token":"{count}"}}"#))
+      };
+
+      guest.token_with(&client, &mut send).unwrap();
+      guest.token_with(&client, &mut send).unwrap();
+      assert!(guest.cached().is_none());
+      assert_eq!(count, 2);
+   }
+}
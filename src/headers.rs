@@ -0,0 +1,284 @@
+//! The headers X's web client sends with each API request.
+
+use crate::{
+   error::Error,
+   transaction::ClientTransaction,
+};
+
+/// Desktop Chrome, the browser the web client is most often seen from.
+pub const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+                              (KHTML, like Gecko) Chrome/133.0.0.0 Safari/537.36";
+
+/// Builds the full header set of an API request, as the web client sends it.
+///
+/// ```ignore
+/// let headers = RequestHeaders::new(&client, "GET", &path)
+///    .with_bearer_token(&context.bearer_token)
+///    .with_cookies("auth_token=...; ct0=...")
+///    .build();
+/// ```
+///
+/// The headers are, in order:
+///
+/// - `authorization`, if a bearer token is set.
+/// - `cookie`, if cookies are set.
+/// - `user-agent`.
+/// - `x-client-transaction-id`, generated for the method and path.
+/// - `x-csrf-token`, the `ct0` cookie, if there is one.
+/// - `x-guest-token`, if a guest token is set and there is no `auth_token`
+///   cookie.
+/// - `x-twitter-active-user`.
+/// - `x-twitter-auth-type`, if there is an `auth_token` cookie.
+/// - `x-twitter-client-language`.
+///
+/// Body headers such as `content-type` are left to the caller.
+#[derive(Debug, Clone)]
+pub struct RequestHeaders<'a> {
+   client:       &'a ClientTransaction,
+   method:       String,
+   path:         String,
+   bearer_token: Option<String>,
+   cookies:      Option<String>,
+   guest_token:  Option<String>,
+   user_agent:   String,
+   language:     String,
+   active_user:  bool,
+}
+
+impl<'a> RequestHeaders<'a> {
+   /// Headers for a `method` request to `path`, e.g.
+   /// `/i/api/graphql/<id>/UserByScreenName?variables=...`. The query string
+   /// is not part of the transaction ID.
+   #[must_use]
+   pub fn new(client: &'a ClientTransaction, method: &str, path: &str) -> Self {
+      Self {
+         client,
+         method: method.to_ascii_uppercase(),
+         path: path.to_owned(),
+         bearer_token: None,
+         cookies: None,
+         guest_token: None,
+         user_agent: USER_AGENT.to_owned(),
+         language: "en".to_owned(),
+         active_user: true,
+      }
+   }
+
+   /// Sends `authorization: Bearer <token>`, e.g. with
+   /// [`WebClientContext::bearer_token`](crate::WebClientContext::bearer_token).
+   #[must_use]
+   pub fn with_bearer_token(mut self, bearer_token: impl Into<String>) -> Self {
+      self.bearer_token = Some(bearer_token.into());
+      self
+   }
+
+   /// Sends the session's `Cookie` header, e.g. `auth_token=...; ct0=...`.
+   /// The CSRF token is read from its `ct0` cookie.
+   #[must_use]
+   pub fn with_cookies(mut self, cookies: impl Into<String>) -> Self {
+      self.cookies = Some(cookies.into());
+      self
+   }
+
+   /// Sends `x-guest-token` for logged-out requests.
+   #[must_use]
+   pub fn with_guest_token(mut self, guest_token: impl Into<String>) -> Self {
+      self.guest_token = Some(guest_token.into());
+      self
+   }
+
+   /// Sends this `user-agent` instead of desktop Chrome.
+   #[must_use]
+   pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+      self.user_agent = user_agent.into();
+      self
+   }
+
+   /// Sends this `x-twitter-client-language` instead of `en`.
+   #[must_use]
+   pub fn with_language(mut self, language: impl Into<String>) -> Self {
+      self.language = language.into();
+      self
+   }
+
+   /// Sends `x-twitter-active-user: no`, as the web client does for
+   /// background polling.
+   #[must_use]
+   pub const fn with_background(mut self) -> Self {
+      self.active_user = false;
+      self
+   }
+
+   /// The path the transaction ID is generated for: the request path
+   /// without its query string or fragment.
+   #[must_use]
+   pub fn id_path(&self) -> &str {
+      self.path.split(['?', '#']).next().unwrap_or_default()
+   }
+
+   /// The headers, with a transaction ID for the current time.
+   #[must_use]
+   pub fn build(&self) -> Vec<(String, String)> {
+      self.headers(
+         self
+            .client
+            .generate_transaction_id(&self.method, self.id_path()),
+      )
+   }
+
+   /// Like [`build`](Self::build), but fails if the clock is outside the
+   /// range of [`XTimestamp`](crate::XTimestamp).
+   pub fn try_build(&self) -> Result<Vec<(String, String)>, Error> {
+      Ok(self.headers(
+         self
+            .client
+            .try_generate_transaction_id(&self.method, self.id_path())?,
+      ))
+   }
+
+   /// The headers, with a transaction ID for a fixed time in seconds since
+   /// X's epoch. See
+   /// [`generate_transaction_id_at`](ClientTransaction::generate_transaction_id_at).
+   #[must_use]
+   pub fn build_at(&self, time: u32) -> Vec<(String, String)> {
+      self.headers(
+         self
+            .client
+            .generate_transaction_id_at(&self.method, self.id_path(), time),
+      )
+   }
+
+   fn headers(&self, transaction_id: String) -> Vec<(String, String)> {
+      let cookies = self.cookies.as_deref().unwrap_or_default();
+      let logged_in = cookie(cookies, "auth_token").is_some();
+
+      let mut headers = Vec::with_capacity(9);
+      let mut push = |name: &str, value: String| headers.push((name.to_owned(), value));
+
+      if let Some(ref token) = self.bearer_token {
+         push("authorization", format!("Bearer {token}"));
+      }
+      if let Some(ref cookies) = self.cookies {
+         push("cookie", cookies.clone());
+      }
+      push("user-agent", self.user_agent.clone());
+      push("x-client-transaction-id", transaction_id);
+      if let Some(csrf) = cookie(cookies, "ct0") {
+         push("x-csrf-token", csrf.to_owned());
+      }
+      if let Some(ref token) = self.guest_token
+         && !logged_in
+      {
+         push("x-guest-token", token.clone());
+      }
+      push(
+         "x-twitter-active-user",
+         if self.active_user { "yes" } else { "no" }.to_owned(),
+      );
+      if logged_in {
+         push("x-twitter-auth-type", "OAuth2Session".to_owned());
+      }
+      push("x-twitter-client-language", self.language.clone());
+
+      headers
+   }
+}
+
+/// Value of the cookie `name` in a `Cookie` header.
+pub fn cookie<'a>(cookies: &'a str, name: &str) -> Option<&'a str> {
+   cookies.split(';').find_map(|pair| {
+      let (key, value) = pair.split_once('=')?;
+      (key.trim() == name)
+         .then(|| value.trim())
+         .filter(|value| !value.is_empty())
+   })
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::{
+      DecodedTransactionId,
+      testing::SyntheticPages,
+   };
+
+   fn client() -> ClientTransaction {
+      let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>());
+      ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap()
+   }
+
+   fn names(headers: &[(String, String)]) -> Vec<&str> {
+      headers.iter().map(|&(ref name, _)| name.as_str()).collect()
+   }
+
+   fn value<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+      headers
+         .iter()
+         .find(|&&(ref key, _)| key == name)
+         .map(|&(_, ref value)| value.as_str())
+   }
+
+   #[test]
+   fn logged_in() {
+      let client = client();
+      let headers = RequestHeaders::new(&client, "get", "/i/api/graphql/q1/Op?variables=%7B%7D")
+         .with_bearer_token("bearer")
+         .with_cookies("guest_id=v1; auth_token=secret; ct0=csrf123")
+         .with_guest_token("123")
+         .build_at(1_000);
+
+      assert_eq!(names(&headers), [
+         "authorization",
+         "cookie",
+         "user-agent",
+         "x-client-transaction-id",
+         "x-csrf-token",
+         "x-twitter-active-user",
+         "x-twitter-auth-type",
+         "x-twitter-client-language",
+      ]);
+      assert_eq!(value(&headers, "authorization"), Some("Bearer bearer"));
+      assert_eq!(value(&headers, "x-csrf-token"), Some("csrf123"));
+      assert_eq!(value(&headers, "x-twitter-active-user"), Some("yes"));
+      assert_eq!(
+         value(&headers, "x-client-transaction-id").unwrap(),
+         client.generate_transaction_id_at("GET", "/i/api/graphql/q1/Op", 1_000)
+      );
+   }
+
+   #[test]
+   fn logged_out() {
+      let client = client();
+      let headers = RequestHeaders::new(&client, "POST", "/1.1/jot/client_event.json")
+         .with_guest_token("123")
+         .with_language("de")
+         .with_background()
+         .try_build()
+         .unwrap();
+
+      assert_eq!(names(&headers), [
+         "user-agent",
+         "x-client-transaction-id",
+         "x-guest-token",
+         "x-twitter-active-user",
+         "x-twitter-client-language",
+      ]);
+      assert_eq!(value(&headers, "x-twitter-active-user"), Some("no"));
+      assert_eq!(value(&headers, "x-twitter-client-language"), Some("de"));
+
+      let id = value(&headers, "x-client-transaction-id").unwrap();
+      assert_eq!(
+         DecodedTransactionId::decode(id).unwrap().key_bytes,
+         client.key_bytes()
+      );
+   }
+
+   #[test]
+   fn cookie_values() {
+      let cookies = "ct0=abc; auth_token = def ;empty=; =odd";
+      assert_eq!(cookie(cookies, "ct0"), Some("abc"));
+      assert_eq!(cookie(cookies, "auth_token"), Some("def"));
+      assert_eq!(cookie(cookies, "empty"), None);
+      assert_eq!(cookie(cookies, "missing"), None);
+   }
+}
@@ -18,12 +18,44 @@
 //! let client = ClientTransaction::new(&html, &js)?;
 //! ```
 
-mod cubic_curve;
+pub mod algorithm;
+pub mod animation;
+#[cfg(feature = "fetch")] mod cache;
+pub mod canary;
+mod context;
+mod decode;
+pub mod discovery;
 mod error;
-mod interpolate;
-mod rotation;
+#[cfg(feature = "fetch")] mod fetch;
+pub mod graphql;
+#[cfg(feature = "guest")] pub mod guest;
+mod headers;
+pub mod manifest;
+mod ondemand;
+pub mod parse;
+mod pool;
+#[cfg(feature = "fetch")] mod retry;
+mod session;
+#[cfg(any(test, feature = "testing"))] pub mod testing;
+mod timestamp;
 mod transaction;
 mod utils;
 
+#[cfg(feature = "fetch")] pub use cache::FileCache;
+pub use context::WebClientContext;
+pub use decode::DecodedTransactionId;
 pub use error::Error;
-pub use transaction::ClientTransaction;
+#[cfg(feature = "fetch")] pub use fetch::FetchOptions;
+pub use headers::RequestHeaders;
+pub use ondemand::OndemandIndices;
+pub use pool::{
+   AccountStats,
+   ClientPool,
+};
+#[cfg(feature = "fetch")] pub use retry::RetryPolicy;
+pub use session::Session;
+pub use timestamp::XTimestamp;
+pub use transaction::{
+   ClientTransaction,
+   StalePolicy,
+};
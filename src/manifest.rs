@@ -0,0 +1,240 @@
+//! Webpack chunk manifest from X's homepage.
+//!
+//! The homepage's webpack runtime maps every lazily loaded chunk to a content
+//! hash, either directly (`{"ondemand.s":"5a1b2c3",...}`) or through chunk IDs
+//! (`{8712:"ondemand.s"}` next to `{8712:"5a1b2c3"}`). Entry scripts such as
+//! `main.js` are plain `<script src>` tags instead. [`ChunkManifest`] collects
+//! both, so the URL of any chunk can be built by name.
+
+use std::collections::BTreeMap;
+
+use crate::{
+   discovery::{
+      chunk_map,
+      is_hash,
+      quoted,
+   },
+   error::Error,
+};
+
+/// Directory X serves its web client chunks from.
+pub const CHUNK_BASE_URL: &str = "https://abs.twimg.com/responsive-web/client-web/";
+
+/// Entry scripts whose directory the lazily loaded chunks share.
+const BUNDLE_SCRIPTS: [&str; 2] = ["main", "vendor"];
+
+/// Chunk names and hashes found on the homepage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[expect(
+   clippy::module_name_repetitions,
+   reason = "`Manifest` alone is ambiguous in callers' imports"
+)]
+pub struct ChunkManifest {
+   base_url: String,
+   chunks:   BTreeMap<String, String>,
+   scripts:  BTreeMap<String, String>,
+}
+
+impl ChunkManifest {
+   /// Parses the chunk maps and entry `<script src>` tags of homepage HTML.
+   ///
+   /// Chunks are built under the directory `main.js` (or else `vendor.js`)
+   /// was served from, or [`CHUNK_BASE_URL`] if the page loads neither, so
+   /// third-party scripts never decide where chunks come from.
+   pub fn parse(home_page_html: &str) -> Result<Self, Error> {
+      let scripts = entry_scripts(home_page_html);
+      let base_url = BUNDLE_SCRIPTS
+         .iter()
+         .find_map(|&name| scripts.get(name))
+         .and_then(|url| url.rfind('/').map(|end| url[..=end].to_owned()))
+         .unwrap_or_else(|| CHUNK_BASE_URL.to_owned());
+
+      let manifest = Self {
+         base_url,
+         chunks: chunk_map(home_page_html),
+         scripts,
+      };
+
+      if manifest.chunks.is_empty() && manifest.scripts.is_empty() {
+         return Err(Error::MissingKey("webpack chunk manifest".into()));
+      }
+      Ok(manifest)
+   }
+
+   /// Builds chunk URLs under `base_url` instead of the directory the entry
+   /// scripts were served from, e.g. to go through a mirror.
+   #[must_use]
+   pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+      self.base_url = base_url.into();
+      if !self.base_url.ends_with('/') {
+         self.base_url.push('/');
+      }
+      self
+   }
+
+   /// Directory chunk URLs are built under.
+   #[must_use]
+   pub fn base_url(&self) -> &str {
+      &self.base_url
+   }
+
+   /// Hash of a lazily loaded chunk, e.g. `ondemand.s`.
+   #[must_use]
+   pub fn hash(&self, name: &str) -> Option<&str> {
+      self.chunks.get(name).map(String::as_str)
+   }
+
+   /// URL of a chunk or entry script, e.g. `ondemand.s` or `main`.
+   ///
+   /// Entry scripts keep the URL they were loaded from; lazily loaded chunks
+   /// are `<base_url><name>.<hash>a.js`, the naming X's runtime uses.
+   #[must_use]
+   pub fn url(&self, name: &str) -> Option<String> {
+      if let Some(url) = self.scripts.get(name) {
+         return Some(url.clone());
+      }
+      self
+         .hash(name)
+         .map(|hash| format!("{}{name}.{hash}a.js", self.base_url))
+   }
+
+   /// URL of `main.js`, which holds the bearer token and most GraphQL
+   /// operations.
+   #[must_use]
+   pub fn main_url(&self) -> Option<String> {
+      self.url("main")
+   }
+
+   /// Names of the lazily loaded chunks, in sorted order.
+   pub fn chunk_names(&self) -> impl Iterator<Item = &str> {
+      self.chunks.keys().map(String::as_str)
+   }
+
+   /// Names of the entry scripts, in sorted order.
+   pub fn script_names(&self) -> impl Iterator<Item = &str> {
+      self.scripts.keys().map(String::as_str)
+   }
+}
+
+/// Name → URL of each `<script src>` named like `<name>.<hash>.js`.
+fn entry_scripts(html: &str) -> BTreeMap<String, String> {
+   let mut scripts = BTreeMap::new();
+
+   for (pos, _) in html.match_indices("<script") {
+      let tag = &html[pos..];
+      let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+      let Some(src) = tag
+         .find("src=")
+         .and_then(|start| quoted(&tag[start + 4..]))
+         .map(|(src, _)| src)
+      else {
+         continue;
+      };
+
+      let url = if let Some(rest) = src.strip_prefix("//") {
+         format!("https://{rest}")
+      } else if src.starts_with("https://") || src.starts_with("http://") {
+         src.to_owned()
+      } else {
+         continue;
+      };
+
+      let file = url.rsplit('/').next().unwrap_or_default();
+      let Some((name, hash)) = file
+         .strip_suffix(".js")
+         .and_then(|stem| stem.rsplit_once('.'))
+      else {
+         continue;
+      };
+
+      if !name.is_empty() && is_hash(hash) {
+         scripts.insert(name.to_owned(), url.clone());
+      }
+   }
+
+   scripts
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::testing::SyntheticPages;
+
+   #[test]
+   fn named_chunk_map() {
+      let html = SyntheticPages::new([0_u8; 48])
+         .with_ondemand_hash("abc123")
+         .home_page_html();
+      let manifest = ChunkManifest::parse(&html).unwrap();
+
+      assert_eq!(manifest.base_url(), CHUNK_BASE_URL);
+      assert_eq!(manifest.hash("ondemand.s"), Some("abc123"));
+      assert_eq!(
+         manifest.url("loader.AppModules").unwrap(),
+         "https://abs.twimg.com/responsive-web/client-web/loader.AppModules.52b6e0fa.js"
+      );
+      assert_eq!(manifest.chunk_names().collect::<Vec<_>>(), [
+         "loader.AppModules",
+         "ondemand.s",
+         "vendor"
+      ]);
+      assert_eq!(manifest.url("missing"), None);
+   }
+
+   #[test]
+   fn numeric_chunk_map() {
+      let html = r#"e=>e+"."+{20:"vendor",8712:"ondemand.s",99:"bundle.Api"}[e]+"."+{20:"f00",8712:"beef42",99:"d1ce"}[e]+"a.js""#;
+      let manifest = ChunkManifest::parse(html).unwrap();
+
+      assert_eq!(manifest.hash("ondemand.s"), Some("beef42"));
+      assert_eq!(manifest.hash("bundle.Api"), Some("d1ce"));
+      assert_eq!(manifest.hash("vendor"), Some("f00"));
+   }
+
+   #[test]
+   fn entry_scripts_and_base_url() {
+      let html = r#"<script type="text/javascript" charset="utf-8" nonce="x" crossorigin="anonymous" src="https://mirror.example/client-web/main.8f9e7d6a.js"></script><script>{"ondemand.s":"abc123"}</script>"#;
+      let manifest = ChunkManifest::parse(html).unwrap();
+
+      assert_eq!(
+         manifest.main_url().unwrap(),
+         "https://mirror.example/client-web/main.8f9e7d6a.js"
+      );
+      assert_eq!(manifest.script_names().collect::<Vec<_>>(), ["main"]);
+      assert_eq!(
+         manifest.url("ondemand.s").unwrap(),
+         "https://mirror.example/client-web/ondemand.s.abc123a.js"
+      );
+
+      let manifest = manifest.with_base_url("http://localhost:8080/assets");
+      assert_eq!(
+         manifest.url("ondemand.s").unwrap(),
+         "http://localhost:8080/assets/ondemand.s.abc123a.js"
+      );
+   }
+
+   #[test]
+   fn third_party_scripts_keep_base_url() {
+      let html = r#"<script src="https://cdn.polyfill.example/a/analytics.1f2e3d.js"></script><script>{"ondemand.s":"abc123"}</script>"#;
+      let manifest = ChunkManifest::parse(html).unwrap();
+      assert_eq!(manifest.base_url(), CHUNK_BASE_URL);
+
+      let html = format!(
+         r#"{html}<script src="https://abs.twimg.com/responsive-web/client-web-legacy/vendor.9a8b7c6a.js"></script>"#
+      );
+      let manifest = ChunkManifest::parse(&html).unwrap();
+      assert_eq!(
+         manifest.base_url(),
+         "https://abs.twimg.com/responsive-web/client-web-legacy/"
+      );
+      assert_eq!(
+         manifest.url("analytics").unwrap(),
+         "https://cdn.polyfill.example/a/analytics.1f2e3d.js"
+      );
+   }
+
+   #[test]
+   fn no_manifest() {
+      ChunkManifest::parse("<html></html>").unwrap_err();
+   }
+}
@@ -0,0 +1,143 @@
+//! Key byte indices from the ondemand.s chunk.
+
+use std::{
+   fmt,
+   str::FromStr,
+};
+
+use crate::error::Error;
+
+/// Which verification key bytes drive the loading animation, as read from
+/// ondemand.js.
+///
+/// The chunk is named after its hash, so indices parsed once can be reused
+/// for as long as the homepage points at the same hash; see
+/// [`ClientTransaction::reusable_indices`](crate::ClientTransaction::reusable_indices).
+///
+/// Formats as the row index followed by the key byte indices, separated by
+/// spaces (`2 12 14 7`), and parses back from the same form.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OndemandIndices {
+   row_index:        usize,
+   key_byte_indices: Vec<usize>,
+}
+
+impl OndemandIndices {
+   #[must_use]
+   pub const fn new(row_index: usize, key_byte_indices: Vec<usize>) -> Self {
+      Self {
+         row_index,
+         key_byte_indices,
+      }
+   }
+
+   /// Finds the `(e[N], 16)` calls in ondemand.js. The first is the row
+   /// index, the rest are the key byte indices.
+   pub fn parse(ondemand_js: &str) -> Result<Self, Error> {
+      let mut indices = Vec::new();
+      let bytes = ondemand_js.as_bytes();
+
+      for idx in 0..bytes.len().saturating_sub(10) {
+         if bytes.get(idx) == Some(&b'(') && bytes.get(idx + 2) == Some(&b'[') {
+            let start = idx + 3;
+            let mut end = start;
+            while end < bytes.len() && bytes.get(end).is_some_and(u8::is_ascii_digit) {
+               end += 1;
+            }
+
+            if end > start
+               && let Some(rest) = ondemand_js.get(end..)
+               && (rest.starts_with("], 16)") || rest.starts_with("],16)"))
+               && let Some(num_str) = ondemand_js.get(start..end)
+               && let Ok(num) = num_str.parse::<usize>()
+            {
+               indices.push(num);
+            }
+         }
+      }
+
+      let Some((&row_index, key_byte_indices)) = indices.split_first() else {
+         return Err(Error::MissingKey("key byte indices".into()));
+      };
+
+      Ok(Self::new(row_index, key_byte_indices.to_vec()))
+   }
+
+   /// Index of the key byte that selects the animation row.
+   #[must_use]
+   pub const fn row_index(&self) -> usize {
+      self.row_index
+   }
+
+   /// Indices of the key bytes that determine the animation time.
+   #[must_use]
+   pub fn key_byte_indices(&self) -> &[usize] {
+      &self.key_byte_indices
+   }
+}
+
+impl fmt::Display for OndemandIndices {
+   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      write!(f, "{}", self.row_index)?;
+      for index in &self.key_byte_indices {
+         write!(f, " {index}")?;
+      }
+      Ok(())
+   }
+}
+
+impl FromStr for OndemandIndices {
+   type Err = Error;
+
+   fn from_str(s: &str) -> Result<Self, Self::Err> {
+      let indices = s
+         .split_whitespace()
+         .map(|index| {
+            index
+               .parse::<usize>()
+               .map_err(|_| Error::Parse(format!("invalid index `{index}`")))
+         })
+         .collect::<Result<Vec<_>, _>>()?;
+
+      let Some((&row_index, key_byte_indices)) = indices.split_first() else {
+         return Err(Error::Parse("empty index list".into()));
+      };
+
+      Ok(Self::new(row_index, key_byte_indices.to_vec()))
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn indices_parsing() {
+      let js = "foo(e[5], 16)bar(e[10], 16)padding";
+      let indices = OndemandIndices::parse(js).unwrap();
+      assert_eq!(indices.row_index(), 5);
+      assert_eq!(indices.key_byte_indices(), [10]);
+   }
+
+   #[test]
+   fn indices_missing() {
+      OndemandIndices::parse("no indices here").unwrap_err();
+   }
+
+   #[test]
+   fn text_round_trip() {
+      let indices = OndemandIndices::new(2, vec![12, 14, 7]);
+      assert_eq!(indices.to_string(), "2 12 14 7");
+      assert_eq!("2 12 14 7".parse::<OndemandIndices>().unwrap(), indices);
+      assert_eq!(
+         "3".parse::<OndemandIndices>().unwrap(),
+         OndemandIndices::new(3, Vec::new())
+      );
+   }
+
+   #[test]
+   fn text_invalid() {
+      "".parse::<OndemandIndices>().unwrap_err();
+      "2 x 7".parse::<OndemandIndices>().unwrap_err();
+   }
+}
@@ -0,0 +1,219 @@
+//! Parsers for the pieces of key material on X's pages.
+//!
+//! [`ClientTransaction::new`](crate::ClientTransaction::new) runs all of them;
+//! call them directly to cache or swap out individual pieces and build the
+//! client with
+//! [`ClientTransaction::from_parts`](crate::ClientTransaction::from_parts).
+//! The ondemand.js indices are parsed by
+//! [`OndemandIndices::parse`](crate::OndemandIndices::parse).
+
+use crate::{
+   error::Error,
+   utils::base64_decode,
+};
+
+/// Reads the base64 `twitter-site-verification` key from homepage HTML.
+pub fn verification_key(html: &str) -> Result<String, Error> {
+   let marker = "name=\"twitter-site-verification\"";
+   let pos = html
+      .find(marker)
+      .ok_or_else(|| Error::MissingKey("twitter-site-verification meta tag".into()))?;
+
+   let tag_start = html
+      .get(..pos)
+      .and_then(|slice| slice.rfind('<'))
+      .unwrap_or(0);
+
+   let tag_end = html
+      .get(pos..)
+      .and_then(|slice| slice.find('>'))
+      .map_or(html.len(), |offset| pos + offset);
+
+   let tag = html
+      .get(tag_start..tag_end)
+      .ok_or_else(|| Error::Parse("malformed meta tag".into()))?;
+
+   let content_marker = "content=\"";
+   let content_pos = tag
+      .find(content_marker)
+      .ok_or_else(|| Error::MissingKey("content attribute".into()))?;
+
+   let value_start = content_pos + content_marker.len();
+   let value_end = tag
+      .get(value_start..)
+      .and_then(|slice| slice.find('"'))
+      .ok_or_else(|| Error::Parse("malformed content attribute".into()))?;
+
+   tag.get(value_start..value_start + value_end)
+      .map(ToOwned::to_owned)
+      .ok_or_else(|| Error::Parse("could not extract verification key".into()))
+}
+
+/// Decodes a base64 verification key to the bytes a
+/// [`ClientTransaction`](crate::ClientTransaction) is built from.
+pub fn decode_verification_key(key: &str) -> Result<Vec<u8>, Error> {
+   base64_decode(key).map_err(Error::from)
+}
+
+/// Returns the `d` attribute of the curve path in each `loading-x-anim-N`
+/// SVG, in page order, e.g. for
+/// [`render_svg`](crate::animation::render_svg).
+#[must_use]
+pub fn animation_paths(html: &str) -> Vec<String> {
+   let mut frames = Vec::new();
+   let mut search_pos = 0;
+
+   while let Some(pos) = html
+      .get(search_pos..)
+      .and_then(|slice| slice.find("id=\"loading-x-anim"))
+   {
+      let abs_pos = search_pos + pos;
+
+      let Some(svg_content) = html
+         .get(abs_pos..)
+         .and_then(|slice| slice.find("</svg>").map(|end| &slice[..end]))
+      else {
+         break;
+      };
+
+      let mut path_search = 0;
+      let mut found_curve_path = false;
+
+      while let Some(path_pos) = svg_content
+         .get(path_search..)
+         .and_then(|slice| slice.find("<path"))
+      {
+         let path_abs = path_search + path_pos;
+
+         let path_end = svg_content
+            .get(path_abs..)
+            .and_then(|slice| slice.find("/>").or_else(|| slice.find("></path>")));
+
+         if let Some(end_offset) = path_end
+            && let Some(path_tag) = svg_content.get(path_abs..path_abs + end_offset)
+            && let Some(d_value) = extract_path_d(path_tag)
+            && d_value.contains('C')
+            && !found_curve_path
+         {
+            frames.push(d_value.to_owned());
+            found_curve_path = true;
+         }
+         path_search = path_abs + 5;
+      }
+
+      search_pos = abs_pos + svg_content.len();
+   }
+
+   frames
+}
+
+fn extract_path_d(path_tag: &str) -> Option<&str> {
+   let d_pos = path_tag.find(" d=\"")?;
+   let d_start = d_pos + 4;
+   let rest = path_tag.get(d_start..)?;
+   let d_end = rest.find('"')?;
+   rest.get(..d_end)
+}
+
+/// Parses an SVG path `d` attribute into a coordinate table, one row per `C`
+/// segment.
+#[must_use]
+pub fn path_coordinates(path_d: &str) -> Vec<Vec<i32>> {
+   // Skip initial move command ("M0 0 0 0" is 9 chars)
+   let d_content = path_d.get(9..).unwrap_or(path_d);
+
+   d_content
+      .split('C')
+      .map(|segment| {
+         segment
+            .replace(|chr: char| !chr.is_ascii_digit() && chr != '-', " ")
+            .split_whitespace()
+            .filter_map(|token| token.parse::<i32>().ok())
+            .collect()
+      })
+      .collect()
+}
+
+/// Finds the web client's bearer token in main.js.
+///
+/// The token is a string literal starting with a run of `A`s, e.g.
+/// `"AAAAAAAAAAAAAAAAAAAAANRILgAAAAAA...%3D..."`.
+pub fn bearer_token(main_js: &str) -> Result<String, Error> {
+   const PREFIX: &str = "AAAAAAAAAAAAAAAAAAAA";
+
+   for quote in ['"', '\''] {
+      let marker = format!("{quote}{PREFIX}");
+      for (pos, _) in main_js.match_indices(&marker) {
+         let rest = &main_js[pos + 1..];
+         let Some(end) = rest.find(quote) else {
+            continue;
+         };
+
+         let token = &rest[..end];
+         if token.len() >= 80
+            && token
+               .chars()
+               .all(|chr| chr.is_ascii_alphanumeric() || chr == '%')
+         {
+            return Ok(token.to_owned());
+         }
+      }
+   }
+
+   Err(Error::MissingKey("bearer token".into()))
+}
+
+/// Parses the coordinate table of every `loading-x-anim-N` SVG on the
+/// homepage, in page order.
+#[must_use]
+pub fn animation_frames(html: &str) -> Vec<Vec<Vec<i32>>> {
+   animation_paths(html)
+      .iter()
+      .map(|path_d| path_coordinates(path_d))
+      .collect()
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::testing::{
+      BEARER_TOKEN,
+      SyntheticPages,
+      main_js,
+   };
+
+   #[test]
+   fn verification_key_extraction() {
+      let html = r#"<html><head><meta name="twitter-site-verification" content="abc123xyz"/></head></html>"#;
+      let key = verification_key(html).unwrap();
+      assert_eq!(key, "abc123xyz");
+   }
+
+   #[test]
+   fn verification_key_missing() {
+      let html = "<html><head></head></html>";
+      verification_key(html).unwrap_err();
+   }
+
+   #[test]
+   fn parse_path_coordinates() {
+      let path = "M0 0 0 0C10 20 30 40 50 60C70 80 90 100 110 120";
+      let result = path_coordinates(path);
+      assert_eq!(result.len(), 2);
+      assert!(!result[0].is_empty());
+      assert!(!result[1].is_empty());
+   }
+
+   #[test]
+   fn bearer_token_extraction() {
+      assert_eq!(bearer_token(&main_js()).unwrap(), BEARER_TOKEN);
+      bearer_token("const t=\"AAAAAAAAAAAAAAAAAAAAAAAA\"").unwrap_err();
+      bearer_token("no token").unwrap_err();
+   }
+
+   #[test]
+   fn frames_extraction() {
+      let pages = SyntheticPages::new([0_u8; 48]);
+      assert_eq!(animation_frames(&pages.home_page_html()), pages.frames());
+   }
+}
@@ -0,0 +1,422 @@
+//! Transaction clients for many accounts.
+
+use std::{
+   collections::{
+      HashMap,
+      HashSet,
+   },
+   fmt,
+   sync::{
+      Arc,
+      Condvar,
+      Mutex,
+      MutexGuard,
+      PoisonError,
+   },
+   time::{
+      Duration,
+      Instant,
+   },
+};
+
+#[cfg(feature = "fetch")] use crate::fetch::FetchOptions;
+use crate::{
+   error::Error,
+   transaction::ClientTransaction,
+};
+
+/// Creates or refreshes the client of an account, given its previous client
+/// if there is one.
+type Loader =
+   dyn Fn(&str, Option<&ClientTransaction>) -> Result<ClientTransaction, Error> + Send + Sync;
+
+/// Key material age of one account in a [`ClientPool`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountStats {
+   pub account: String,
+   /// [Age](ClientTransaction::age) of the account's key material.
+   pub age:     Duration,
+   /// Time since the client was last handed out.
+   pub idle:    Duration,
+}
+
+/// A [`ClientTransaction`] per account, created on first use and refreshed
+/// once its key material is older than [`max_age`](Self::with_max_age).
+///
+/// Ages come from [`ClientTransaction::age`], so a client restored from an
+/// old session is refreshed on its first use.
+///
+/// Logged-in homepages may carry different key material, so each account's
+/// client should be fetched with its own cookies:
+///
+/// ```ignore
+/// let pool = ClientPool::fetching(FetchOptions::default(), move |account| {
+///    sessions.get(account).map(Session::cookie_header)
+/// });
+/// let client = pool.get("account-1")?;
+/// let id = client.generate_transaction_id("GET", &path);
+/// ```
+///
+/// At most [`max_concurrent_refreshes`](Self::with_max_concurrent_refreshes)
+/// loads run at once; other callers wait. Callers asking for the same
+/// account share one load.
+pub struct ClientPool {
+   loader:                   Box<Loader>,
+   max_age:                  Duration,
+   idle_timeout:             Duration,
+   max_concurrent_refreshes: usize,
+   state:                    Mutex<State>,
+   loaded:                   Condvar,
+}
+
+#[derive(Default)]
+struct State {
+   entries: HashMap<String, Entry>,
+   loading: HashSet<String>,
+}
+
+struct Entry {
+   client:    Arc<ClientTransaction>,
+   last_used: Instant,
+}
+
+impl ClientPool {
+   /// Refresh clients older than this.
+   pub const DEFAULT_MAX_AGE: Duration = Duration::from_mins(30);
+   /// Drop clients not used for this long.
+   pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_hours(2);
+   /// Loads run at once.
+   pub const DEFAULT_MAX_CONCURRENT_REFRESHES: usize = 4;
+
+   /// Loads clients with `loader`, which gets the account ID and the
+   /// account's previous client, if any, e.g. to reuse its indices.
+   pub fn new(
+      loader: impl Fn(&str, Option<&ClientTransaction>) -> Result<ClientTransaction, Error>
+      + Send
+      + Sync
+      + 'static,
+   ) -> Self {
+      Self {
+         loader:                   Box::new(loader),
+         max_age:                  Self::DEFAULT_MAX_AGE,
+         idle_timeout:             Self::DEFAULT_IDLE_TIMEOUT,
+         max_concurrent_refreshes: Self::DEFAULT_MAX_CONCURRENT_REFRESHES,
+         state:                    Mutex::new(State::default()),
+         loaded:                   Condvar::new(),
+      }
+   }
+
+   /// Fetches each account's client with `options` and the account's
+   /// `Cookie` header, as returned by `cookies`. Accounts without cookies
+   /// are fetched logged out.
+   #[cfg(feature = "fetch")]
+   pub fn fetching(
+      options: FetchOptions,
+      cookies: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+   ) -> Self {
+      Self::new(move |account, previous| {
+         let options = FetchOptions {
+            cookies: cookies(account).or_else(|| options.cookies.clone()),
+            ..options.clone()
+         };
+         previous.map_or_else(
+            || ClientTransaction::fetch_with(&options),
+            |client| client.refresh_with(&options),
+         )
+      })
+   }
+
+   #[must_use]
+   pub const fn with_max_age(mut self, max_age: Duration) -> Self {
+      self.max_age = max_age;
+      self
+   }
+
+   #[must_use]
+   pub const fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+      self.idle_timeout = idle_timeout;
+      self
+   }
+
+   /// Runs at most `max` loads at once. Zero is treated as one.
+   #[must_use]
+   pub fn with_max_concurrent_refreshes(mut self, max: usize) -> Self {
+      self.max_concurrent_refreshes = max.max(1);
+      self
+   }
+
+   /// The client of `account`, loading it if it's missing or older than the
+   /// maximum age.
+   ///
+   /// If a refresh fails, the error is returned and the old client is kept
+   /// for the next attempt.
+   pub fn get(&self, account: &str) -> Result<Arc<ClientTransaction>, Error> {
+      let mut state = self.lock();
+      loop {
+         if !state.loading.contains(account) {
+            let now = Instant::now();
+            if let Some(entry) = state.entries.get_mut(account)
+               && entry.client.age() < self.max_age
+            {
+               entry.last_used = now;
+               return Ok(Arc::clone(&entry.client));
+            }
+            if state.loading.len() < self.max_concurrent_refreshes {
+               break;
+            }
+         }
+         state = self
+            .loaded
+            .wait(state)
+            .unwrap_or_else(PoisonError::into_inner);
+      }
+
+      state.loading.insert(account.to_owned());
+      let previous = state
+         .entries
+         .get(account)
+         .map(|entry| Arc::clone(&entry.client));
+      drop(state);
+
+      let guard = Loading {
+         pool: self,
+         account,
+      };
+      let client = Arc::new((self.loader)(account, previous.as_deref())?);
+
+      let now = Instant::now();
+      let mut state = self.lock();
+      Self::evict(&mut state, self.idle_timeout, now);
+      state.entries.insert(account.to_owned(), Entry {
+         client:    Arc::clone(&client),
+         last_used: now,
+      });
+      drop(state);
+      drop(guard);
+      Ok(client)
+   }
+
+   /// Adds or replaces the client of `account`, e.g. one restored from a
+   /// saved [`Session`](crate::Session).
+   pub fn insert(&self, account: &str, client: ClientTransaction) {
+      self.lock().entries.insert(account.to_owned(), Entry {
+         client:    Arc::new(client),
+         last_used: Instant::now(),
+      });
+   }
+
+   /// Drops the client of `account`, so the next [`get`](Self::get) loads a
+   /// new one.
+   pub fn remove(&self, account: &str) -> Option<Arc<ClientTransaction>> {
+      self
+         .lock()
+         .entries
+         .remove(account)
+         .map(|entry| entry.client)
+   }
+
+   /// Drops clients not used within the idle timeout and returns how many.
+   /// Also done whenever a client is loaded.
+   pub fn evict_idle(&self) -> usize {
+      Self::evict(&mut self.lock(), self.idle_timeout, Instant::now())
+   }
+
+   /// [Age](ClientTransaction::age) of the client of `account`, if it has
+   /// one.
+   #[must_use]
+   pub fn age(&self, account: &str) -> Option<Duration> {
+      self
+         .lock()
+         .entries
+         .get(account)
+         .map(|entry| entry.client.age())
+   }
+
+   /// Age and idle time of every account's client, oldest first.
+   #[must_use]
+   pub fn stats(&self) -> Vec<AccountStats> {
+      let now = Instant::now();
+      let mut stats = self
+         .lock()
+         .entries
+         .iter()
+         .map(|(account, entry)| {
+            AccountStats {
+               account: account.clone(),
+               age:     entry.client.age(),
+               idle:    now.duration_since(entry.last_used),
+            }
+         })
+         .collect::<Vec<_>>();
+      stats.sort_by(|left, right| {
+         right
+            .age
+            .cmp(&left.age)
+            .then_with(|| left.account.cmp(&right.account))
+      });
+      stats
+   }
+
+   #[must_use]
+   pub fn len(&self) -> usize {
+      self.lock().entries.len()
+   }
+
+   #[must_use]
+   pub fn is_empty(&self) -> bool {
+      self.lock().entries.is_empty()
+   }
+
+   fn lock(&self) -> MutexGuard<'_, State> {
+      self.state.lock().unwrap_or_else(PoisonError::into_inner)
+   }
+
+   fn evict(state: &mut State, idle_timeout: Duration, now: Instant) -> usize {
+      let before = state.entries.len();
+      state
+         .entries
+         .retain(|_, entry| now.duration_since(entry.last_used) < idle_timeout);
+      before - state.entries.len()
+   }
+}
+
+impl fmt::Debug for ClientPool {
+   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      f.debug_struct("ClientPool")
+         .field("max_age", &self.max_age)
+         .field("idle_timeout", &self.idle_timeout)
+         .field("max_concurrent_refreshes", &self.max_concurrent_refreshes)
+         .field("accounts", &self.len())
+         .finish_non_exhaustive()
+   }
+}
+
+/// Marks a load as finished and wakes waiting callers, even if the loader
+/// fails or panics.
+struct Loading<'a> {
+   pool:    &'a ClientPool,
+   account: &'a str,
+}
+
+impl Drop for Loading<'_> {
+   fn drop(&mut self) {
+      self.pool.lock().loading.remove(self.account);
+      self.pool.loaded.notify_all();
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use std::{
+      sync::atomic::{
+         AtomicUsize,
+         Ordering,
+      },
+      thread,
+      time::SystemTime,
+   };
+
+   use super::*;
+   use crate::testing::SyntheticPages;
+
+   fn client(seed: u8) -> ClientTransaction {
+      let pages = SyntheticPages::new((seed..seed + 48).collect::<Vec<u8>>());
+      ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap()
+   }
+
+   #[test]
+   fn loads_once_per_account() {
+      let loads = Arc::new(AtomicUsize::new(0));
+      let counter = Arc::clone(&loads);
+      let pool = ClientPool::new(move |account, _| {
+         counter.fetch_add(1, Ordering::SeqCst);
+         Ok(client(if account == "a" { 0 } else { 100 }))
+      });
+
+      let first = pool.get("a").unwrap();
+      assert!(Arc::ptr_eq(&first, &pool.get("a").unwrap()));
+      assert_eq!(pool.get("b").unwrap().key_bytes()[0], 100);
+      assert_eq!(loads.load(Ordering::SeqCst), 2);
+      assert_eq!(pool.len(), 2);
+      assert!(pool.age("a").is_some());
+      assert_eq!(pool.age("c"), None);
+   }
+
+   #[test]
+   fn refreshes_with_previous_client() {
+      let pool = ClientPool::new(|_, previous| {
+         Ok(client(
+            previous.map_or(0, |client| client.key_bytes()[0] + 1),
+         ))
+      })
+      .with_max_age(Duration::ZERO);
+
+      assert_eq!(pool.get("a").unwrap().key_bytes()[0], 0);
+      assert_eq!(pool.get("a").unwrap().key_bytes()[0], 1);
+   }
+
+   #[test]
+   fn failed_refresh_keeps_client() {
+      let pool =
+         ClientPool::new(|_, _| Err(Error::MissingKey("test".into()))).with_max_age(Duration::ZERO);
+      pool.insert("a", client(0));
+
+      pool.get("a").unwrap_err();
+      assert_eq!(pool.len(), 1);
+      assert!(pool.remove("a").is_some());
+      assert!(pool.is_empty());
+   }
+
+   #[test]
+   fn restored_clients_keep_their_age() {
+      let pool = ClientPool::new(|_, _| Ok(client(1))).with_max_age(Duration::from_hours(1));
+      let old = client(0).with_provenance(SystemTime::now() - Duration::from_hours(2), None);
+      pool.insert("a", old);
+
+      assert!(pool.age("a").unwrap() >= Duration::from_hours(2));
+      assert_eq!(pool.get("a").unwrap().key_bytes()[0], 1);
+      assert!(pool.age("a").unwrap() < Duration::from_hours(1));
+   }
+
+   #[test]
+   fn evicts_idle_clients() {
+      let pool = ClientPool::new(|_, _| Ok(client(0))).with_idle_timeout(Duration::ZERO);
+      pool.insert("a", client(0));
+      pool.insert("b", client(0));
+
+      assert_eq!(pool.stats().len(), 2);
+      assert_eq!(pool.evict_idle(), 2);
+      assert!(pool.stats().is_empty());
+   }
+
+   #[test]
+   fn bounds_concurrent_loads() {
+      let running = Arc::new(AtomicUsize::new(0));
+      let peak = Arc::new(AtomicUsize::new(0));
+      let loads = Arc::new(AtomicUsize::new(0));
+
+      let pool = {
+         let (running, peak, loads) = (Arc::clone(&running), Arc::clone(&peak), Arc::clone(&loads));
+         ClientPool::new(move |_, _| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            loads.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(20));
+            running.fetch_sub(1, Ordering::SeqCst);
+            Ok(client(0))
+         })
+         .with_max_concurrent_refreshes(2)
+      };
+
+      thread::scope(|scope| {
+         for index in 0..12_usize {
+            let pool = &pool;
+            scope.spawn(move || pool.get(&format!("account-{}", index % 6)).unwrap());
+         }
+      });
+
+      assert!(peak.load(Ordering::SeqCst) <= 2);
+      assert_eq!(loads.load(Ordering::SeqCst), 6);
+      assert_eq!(pool.len(), 6);
+   }
+}
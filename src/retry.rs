@@ -0,0 +1,193 @@
+//! Retry and backoff policy for fetching key material.
+
+use std::time::{
+   Duration,
+   SystemTime,
+};
+
+use crate::{
+   error::Error,
+   utils::{
+      parse_http_date,
+      random_u64,
+   },
+};
+
+/// When and how often failed requests are retried.
+///
+/// Only transport errors and the statuses in
+/// [`retryable_statuses`](Self::retryable_statuses) are retried; parse errors
+/// and other statuses fail immediately.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+   /// Total number of attempts, including the first. `1` disables retries.
+   pub max_attempts:       u32,
+   /// Delay before the first retry.
+   pub initial_backoff:    Duration,
+   /// Upper bound for any delay. A `Retry-After` longer than this is not
+   /// waited out; the request fails instead.
+   pub max_backoff:        Duration,
+   /// Factor the delay grows by after each retry.
+   pub multiplier:         f64,
+   /// Randomizes each delay between half and all of its value, so many
+   /// clients don't retry in lockstep.
+   pub jitter:             bool,
+   /// HTTP statuses worth retrying.
+   pub retryable_statuses: Vec<i32>,
+}
+
+impl Default for RetryPolicy {
+   fn default() -> Self {
+      Self {
+         max_attempts:       3,
+         initial_backoff:    Duration::from_millis(500),
+         max_backoff:        Duration::from_secs(30),
+         multiplier:         2.0,
+         jitter:             true,
+         retryable_statuses: vec![408, 425, 429, 500, 502, 503, 504],
+      }
+   }
+}
+
+impl RetryPolicy {
+   /// A policy that never retries.
+   #[must_use]
+   pub fn none() -> Self {
+      Self {
+         max_attempts: 1,
+         ..Self::default()
+      }
+   }
+
+   /// Returns `true` if `err` may go away by retrying.
+   #[must_use]
+   pub fn is_retryable(&self, err: &Error) -> bool {
+      match *err {
+         Error::Http(ref err) => {
+            matches!(
+               *err,
+               minreq::Error::IoError(_)
+                  | minreq::Error::RustlsCreateConnection(_)
+                  | minreq::Error::AddressNotFound
+                  | minreq::Error::ProxyConnect
+                  | minreq::Error::MalformedChunkLength
+                  | minreq::Error::MalformedChunkEnd
+                  | minreq::Error::MalformedContentLength
+            )
+         },
+         Error::HttpStatus(code, _) => self.retryable_statuses.contains(&code),
+         _ => false,
+      }
+   }
+
+   /// Delay before retry number `retry` (starting at 1), or `None` if the
+   /// server asked for a longer wait than [`max_backoff`](Self::max_backoff).
+   #[must_use]
+   pub fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Option<Duration> {
+      if let Some(retry_after) = retry_after {
+         return (retry_after <= self.max_backoff).then_some(retry_after);
+      }
+
+      let exponent = i32::try_from(retry.saturating_sub(1)).unwrap_or(i32::MAX);
+      let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+      let backoff = Duration::try_from_secs_f64(backoff)
+         .unwrap_or(self.max_backoff)
+         .min(self.max_backoff);
+
+      if !self.jitter {
+         return Some(backoff);
+      }
+
+      #[expect(
+         clippy::cast_precision_loss,
+         reason = "jitter only needs a rough fraction"
+      )]
+      let fraction = (random_u64() >> 11) as f64 / (1_u64 << 53) as f64;
+      Some(backoff.mul_f64(0.5 + fraction / 2.0))
+   }
+}
+
+/// Parses a `Retry-After` header: either delay seconds or an HTTP date.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+   let value = value.trim();
+   if let Ok(secs) = value.parse::<u64>() {
+      return Some(Duration::from_secs(secs));
+   }
+
+   let date = parse_http_date(value)?;
+   Some(
+      date
+         .duration_since(SystemTime::now())
+         .unwrap_or(Duration::ZERO),
+   )
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn retryable_statuses() {
+      let policy = RetryPolicy::default();
+      assert!(policy.is_retryable(&Error::HttpStatus(429, "x.com")));
+      assert!(policy.is_retryable(&Error::HttpStatus(503, "x.com")));
+      assert!(!policy.is_retryable(&Error::HttpStatus(404, "x.com")));
+      assert!(!policy.is_retryable(&Error::HttpStatus(403, "x.com")));
+   }
+
+   #[test]
+   fn parse_errors_are_fatal() {
+      let policy = RetryPolicy::default();
+      assert!(!policy.is_retryable(&Error::Parse("bad".into())));
+      assert!(!policy.is_retryable(&Error::MissingKey("key".into())));
+   }
+
+   #[test]
+   fn transport_errors() {
+      let policy = RetryPolicy::default();
+      assert!(policy.is_retryable(&Error::Http(minreq::Error::AddressNotFound)));
+      assert!(!policy.is_retryable(&Error::Http(minreq::Error::BadProxy)));
+   }
+
+   #[test]
+   fn exponential_backoff() {
+      let policy = RetryPolicy {
+         jitter: false,
+         ..RetryPolicy::default()
+      };
+      assert_eq!(policy.delay(1, None), Some(Duration::from_millis(500)));
+      assert_eq!(policy.delay(2, None), Some(Duration::from_secs(1)));
+      assert_eq!(policy.delay(3, None), Some(Duration::from_secs(2)));
+      assert_eq!(policy.delay(100, None), Some(Duration::from_secs(30)));
+   }
+
+   #[test]
+   fn jitter_stays_in_range() {
+      let policy = RetryPolicy::default();
+      for _ in 0..100 {
+         let delay = policy.delay(2, None).unwrap();
+         assert!(delay >= Duration::from_millis(500));
+         assert!(delay <= Duration::from_secs(1));
+      }
+   }
+
+   #[test]
+   fn retry_after_is_honored() {
+      let policy = RetryPolicy::default();
+      assert_eq!(
+         policy.delay(1, Some(Duration::from_secs(7))),
+         Some(Duration::from_secs(7))
+      );
+      assert_eq!(policy.delay(1, Some(Duration::from_hours(1))), None);
+   }
+
+   #[test]
+   fn retry_after_formats() {
+      assert_eq!(parse_retry_after("120"), Some(Duration::from_mins(2)));
+      assert_eq!(
+         parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT"),
+         Some(Duration::ZERO)
+      );
+      assert_eq!(parse_retry_after("soon"), None);
+   }
+}
@@ -0,0 +1,541 @@
+//! A logged-in account: its cookies and transaction client.
+
+use std::{
+   fmt,
+   str::FromStr,
+   time::{
+      Duration,
+      SystemTime,
+      UNIX_EPOCH,
+   },
+};
+
+#[cfg(feature = "fetch")] use crate::fetch::FetchOptions;
+use crate::{
+   algorithm::Registry,
+   error::Error,
+   headers::RequestHeaders,
+   ondemand::OndemandIndices,
+   transaction::ClientTransaction,
+   utils::{
+      base64_decode,
+      base64_encode,
+   },
+};
+
+/// Cookie domains sent to X, for filtering cookie files.
+const DOMAINS: [&str; 2] = ["x.com", "twitter.com"];
+
+/// An account's cookies, bearer token and transaction client.
+///
+/// Logged-in homepages may carry different key material than logged-out
+/// ones, so each account keeps its own [`ClientTransaction`], fetched with
+/// its cookies.
+///
+/// ```ignore
+/// let mut session = Session::from_cookie_header("auth_token=...; ct0=...")
+///    .with_bearer_token(&context.bearer_token);
+/// session.refresh_transaction(&FetchOptions::default())?;
+/// let headers = session.headers("GET", &path)?.build();
+///
+/// fs::write("session.txt", session.to_string())?;
+/// let session: Session = fs::read_to_string("session.txt")?.parse()?;
+/// ```
+///
+/// Formats as `key = value` lines (`cookie`, `bearer_token`, and the
+/// transaction client's `version`, `key_bytes`, `animation_key`, `indices`,
+/// `ondemand_hash`, `home_page_hash`, `created_at` and, once synced,
+/// `clock_offset`) and parses back from the same form. The text holds the
+/// account's credentials; store it accordingly.
+#[derive(Clone, Default)]
+pub struct Session {
+   cookies:      Vec<(String, String)>,
+   bearer_token: Option<String>,
+   transaction:  Option<ClientTransaction>,
+}
+
+impl Session {
+   /// Reads the cookies of a `Cookie` header, e.g. `auth_token=...; ct0=...`.
+   #[must_use]
+   pub fn from_cookie_header(cookies: &str) -> Self {
+      let mut session = Self::default();
+      for pair in cookies.split(';') {
+         if let Some((name, value)) = pair.split_once('=') {
+            session.set_cookie(name.trim(), value.trim());
+         }
+      }
+      session
+   }
+
+   /// Reads the x.com and twitter.com cookies of a Netscape cookie file, as
+   /// exported by browsers and written by curl. Expired cookies are skipped.
+   pub fn from_netscape(cookie_file: &str) -> Result<Self, Error> {
+      let now = SystemTime::now()
+         .duration_since(UNIX_EPOCH)
+         .map_or(0, |since| since.as_secs());
+
+      let mut session = Self::default();
+      for (number, line) in cookie_file.lines().enumerate() {
+         let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+         if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+         }
+
+         let fields = line.split('\t').collect::<Vec<_>>();
+         let &[domain, _, _, _, expires, name, value] = fields.as_slice() else {
+            return Err(Error::Parse(format!(
+               "cookie file line {}: expected 7 tab-separated fields",
+               number + 1
+            )));
+         };
+         let expires = expires.parse::<u64>().map_err(|_| {
+            Error::Parse(format!(
+               "cookie file line {}: invalid expiry `{expires}`",
+               number + 1
+            ))
+         })?;
+
+         if is_x_domain(domain) && (expires == 0 || expires > now) {
+            session.set_cookie(name, value);
+         }
+      }
+
+      if session.cookies.is_empty() {
+         return Err(Error::MissingKey("x.com cookies in cookie file".into()));
+      }
+      Ok(session)
+   }
+
+   /// Sends `authorization: Bearer <token>` with every request.
+   #[must_use]
+   pub fn with_bearer_token(mut self, bearer_token: impl Into<String>) -> Self {
+      self.bearer_token = Some(bearer_token.into());
+      self
+   }
+
+   /// Uses `transaction` for this account's transaction IDs.
+   #[must_use]
+   pub fn with_transaction(mut self, transaction: ClientTransaction) -> Self {
+      self.transaction = Some(transaction);
+      self
+   }
+
+   /// Value of the cookie `name`.
+   #[must_use]
+   pub fn cookie(&self, name: &str) -> Option<&str> {
+      self
+         .cookies
+         .iter()
+         .find(|&&(ref key, _)| key == name)
+         .map(|&(_, ref value)| value.as_str())
+   }
+
+   /// Sets the cookie `name`, e.g. from a `Set-Cookie` header. An empty
+   /// value removes it.
+   pub fn set_cookie(&mut self, name: &str, value: &str) {
+      self.cookies.retain(|&(ref key, _)| key != name);
+      if !name.is_empty() && !value.is_empty() {
+         self.cookies.push((name.to_owned(), value.to_owned()));
+      }
+   }
+
+   /// The `Cookie` header for requests to X.
+   #[must_use]
+   pub fn cookie_header(&self) -> String {
+      self
+         .cookies
+         .iter()
+         .map(|&(ref name, ref value)| format!("{name}={value}"))
+         .collect::<Vec<_>>()
+         .join("; ")
+   }
+
+   /// The `x-csrf-token` header, which X expects to match the `ct0` cookie.
+   #[must_use]
+   pub fn csrf_token(&self) -> Option<&str> {
+      self.cookie("ct0")
+   }
+
+   /// The `auth_token` cookie that identifies the account.
+   #[must_use]
+   pub fn auth_token(&self) -> Option<&str> {
+      self.cookie("auth_token")
+   }
+
+   #[must_use]
+   pub fn is_logged_in(&self) -> bool {
+      self.auth_token().is_some()
+   }
+
+   #[must_use]
+   pub fn bearer_token(&self) -> Option<&str> {
+      self.bearer_token.as_deref()
+   }
+
+   /// The account's transaction client, if one was set or fetched.
+   #[must_use]
+   pub const fn transaction(&self) -> Option<&ClientTransaction> {
+      self.transaction.as_ref()
+   }
+
+   pub fn set_transaction(&mut self, transaction: ClientTransaction) {
+      self.transaction = Some(transaction);
+   }
+
+   /// Fetches x.com with this session's cookies and replaces the
+   /// transaction client, reusing its indices if ondemand.js is unchanged.
+   /// Cookies already set in `options` are replaced.
+   #[cfg(feature = "fetch")]
+   pub fn refresh_transaction(
+      &mut self,
+      options: &FetchOptions,
+   ) -> Result<&ClientTransaction, Error> {
+      let options = FetchOptions {
+         cookies: Some(self.cookie_header()).filter(|cookies| !cookies.is_empty()),
+         ..options.clone()
+      };
+      let transaction = match self.transaction {
+         Some(ref transaction) => transaction.refresh_with(&options)?,
+         None => ClientTransaction::fetch_with(&options)?,
+      };
+      Ok(self.transaction.insert(transaction))
+   }
+
+   /// Headers for a request by this account, with its cookies, CSRF token
+   /// and bearer token. Fails if there is no transaction client yet.
+   pub fn headers(&self, method: &str, path: &str) -> Result<RequestHeaders<'_>, Error> {
+      let transaction = self
+         .transaction
+         .as_ref()
+         .ok_or_else(|| Error::MissingKey("session transaction client".into()))?;
+
+      let mut headers = RequestHeaders::new(transaction, method, path);
+      if !self.cookies.is_empty() {
+         headers = headers.with_cookies(self.cookie_header());
+      }
+      if let Some(ref token) = self.bearer_token {
+         headers = headers.with_bearer_token(token.clone());
+      }
+      Ok(headers)
+   }
+
+   /// Parses a session saved with [`to_string`](ToString::to_string),
+   /// looking its algorithm version up in `registry`. Sessions saved from a
+   /// client with a custom version need the registry that version is in;
+   /// [`parse`](str::parse) only knows the built-in ones.
+   pub fn parse_with_registry(text: &str, registry: &Registry) -> Result<Self, Error> {
+      let mut session = Self::default();
+      let mut key_bytes = None;
+      let mut animation_key = None;
+      let mut indices = None;
+      let mut ondemand_hash = None;
+      let mut home_page_hash = None;
+      let mut created_at = None;
+      let mut clock_offset = None;
+      let mut algorithm = None;
+
+      for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+         let Some((key, value)) = line.split_once(" = ") else {
+            return Err(Error::Parse(format!("invalid session line `{line}`")));
+         };
+         match key {
+            "cookie" => {
+               let (name, value) = value
+                  .split_once('=')
+                  .ok_or_else(|| Error::Parse(format!("invalid cookie `{value}`")))?;
+               session.set_cookie(name, value);
+            },
+            "bearer_token" => session.bearer_token = Some(value.to_owned()),
+            "version" => {
+               algorithm = Some(
+                  value
+                     .parse::<u8>()
+                     .ok()
+                     .and_then(|version| registry.get(version))
+                     .ok_or_else(|| Error::Parse(format!("unknown algorithm version `{value}`")))?,
+               );
+            },
+            "key_bytes" => key_bytes = Some(base64_decode(value).map_err(Error::Base64)?),
+            "animation_key" => animation_key = Some(value.to_owned()),
+            "indices" => indices = Some(value.parse::<OndemandIndices>()?),
+            "ondemand_hash" => ondemand_hash = Some(value.to_owned()),
+            "home_page_hash" => home_page_hash = Some(value.to_owned()),
+            "created_at" => {
+               created_at = Some(
+                  value
+                     .parse::<u64>()
+                     .ok()
+                     .and_then(|secs| UNIX_EPOCH.checked_add(Duration::from_secs(secs)))
+                     .ok_or_else(|| Error::Parse(format!("invalid created_at `{value}`")))?,
+               );
+            },
+            "clock_offset" => {
+               clock_offset = Some(
+                  value
+                     .parse::<i64>()
+                     .map_err(|_| Error::Parse(format!("invalid clock_offset `{value}`")))?,
+               );
+            },
+            _ => return Err(Error::Parse(format!("unknown session key `{key}`"))),
+         }
+      }
+
+      match (key_bytes, animation_key, indices) {
+         (Some(key_bytes), Some(animation_key), Some(indices)) => {
+            let transaction = ClientTransaction::from_key_material(
+               key_bytes,
+               animation_key,
+               indices,
+               ondemand_hash,
+            );
+            let transaction = match created_at {
+               Some(created_at) => transaction.with_provenance(created_at, home_page_hash),
+               None => transaction,
+            };
+            let transaction = match algorithm {
+               Some(algorithm) => transaction.with_algorithm(algorithm),
+               None => transaction,
+            };
+            if let Some(offset) = clock_offset {
+               transaction.set_clock_offset(offset);
+            }
+            session.transaction = Some(transaction);
+         },
+         (None, None, None) => {},
+         _ => {
+            return Err(Error::MissingKey(
+               "session transaction client fields".into(),
+            ));
+         },
+      }
+
+      Ok(session)
+   }
+}
+
+fn is_x_domain(domain: &str) -> bool {
+   let domain = domain.trim_start_matches('.');
+   DOMAINS.iter().any(|&x_domain| {
+      domain == x_domain
+         || domain
+            .strip_suffix(x_domain)
+            .is_some_and(|sub| sub.ends_with('.'))
+   })
+}
+
+impl fmt::Debug for Session {
+   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      let names = self
+         .cookies
+         .iter()
+         .map(|&(ref name, _)| name.as_str())
+         .collect::<Vec<_>>();
+      f.debug_struct("Session")
+         .field("cookies", &names)
+         .field("has_bearer_token", &self.bearer_token.is_some())
+         .field("transaction", &self.transaction)
+         .finish()
+   }
+}
+
+impl fmt::Display for Session {
+   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      for &(ref name, ref value) in &self.cookies {
+         writeln!(f, "cookie = {name}={value}")?;
+      }
+      if let Some(ref token) = self.bearer_token {
+         writeln!(f, "bearer_token = {token}")?;
+      }
+      if let Some(ref transaction) = self.transaction {
+         writeln!(f, "version = {}", transaction.algorithm().version())?;
+         writeln!(f, "key_bytes = {}", base64_encode(transaction.key_bytes()))?;
+         writeln!(f, "animation_key = {}", transaction.animation_key())?;
+         writeln!(f, "indices = {}", transaction.indices())?;
+         if let Some(hash) = transaction.ondemand_hash() {
+            writeln!(f, "ondemand_hash = {hash}")?;
+         }
+         if let Some(hash) = transaction.home_page_hash() {
+            writeln!(f, "home_page_hash = {hash}")?;
+         }
+         let created_at = transaction
+            .created_at()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+         writeln!(f, "created_at = {created_at}")?;
+         if transaction.is_clock_synced() {
+            writeln!(f, "clock_offset = {}", transaction.clock_offset())?;
+         }
+      }
+      Ok(())
+   }
+}
+
+impl FromStr for Session {
+   type Err = Error;
+
+   fn from_str(s: &str) -> Result<Self, Self::Err> {
+      Self::parse_with_registry(s, &Registry::default())
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use std::sync::Arc;
+
+   use super::*;
+   use crate::{
+      algorithm::{
+         TransactionAlgorithm,
+         V3,
+      },
+      testing::SyntheticPages,
+   };
+
+   fn client() -> ClientTransaction {
+      let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>());
+      ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap()
+   }
+
+   #[test]
+   fn cookie_header() {
+      let mut session = Session::from_cookie_header(" auth_token=abc; ct0=def;guest_id=v1%3A1 ");
+      assert!(session.is_logged_in());
+      assert_eq!(session.csrf_token(), Some("def"));
+      assert_eq!(session.cookie("guest_id"), Some("v1%3A1"));
+
+      session.set_cookie("ct0", "new");
+      session.set_cookie("guest_id", "");
+      assert_eq!(session.cookie_header(), "auth_token=abc; ct0=new");
+   }
+
+   #[test]
+   fn netscape_file() {
+      let file = [
+         "# Netscape HTTP Cookie File",
+         "",
+         "#HttpOnly_.x.com\tTRUE\t/\tTRUE\t0\tauth_token\tabc",
+         ".x.com\tTRUE\t/\tTRUE\t4102444800\tct0\tdef",
+         ".x.com\tTRUE\t/\tTRUE\t1\told\tgone",
+         "example.com\tFALSE\t/\tFALSE\t0\tauth_token\tother",
+         "api.twitter.com\tFALSE\t/\tFALSE\t0\tlang\ten",
+      ]
+      .join("\n");
+      let session = Session::from_netscape(&file).unwrap();
+      assert_eq!(session.cookie_header(), "auth_token=abc; ct0=def; lang=en");
+
+      Session::from_netscape("example.com\tFALSE\t/\tFALSE\t0\ta\tb\n").unwrap_err();
+      Session::from_netscape(".x.com\tTRUE\t/\tTRUE\tsoon\tct0\tdef\n").unwrap_err();
+      Session::from_netscape(".x.com\tTRUE\t/\tct0\tdef\n").unwrap_err();
+   }
+
+   #[test]
+   fn headers() {
+      let session =
+         Session::from_cookie_header("auth_token=abc; ct0=def").with_bearer_token("bearer");
+      session.headers("GET", "/").unwrap_err();
+
+      let session = session.with_transaction(client());
+      let headers = session
+         .headers("GET", "/i/api/1.1/account/settings.json")
+         .unwrap()
+         .build();
+      assert!(headers.contains(&("x-csrf-token".to_owned(), "def".to_owned())));
+      assert!(headers.contains(&("authorization".to_owned(), "Bearer bearer".to_owned())));
+      assert!(headers.contains(&("cookie".to_owned(), "auth_token=abc; ct0=def".to_owned())));
+   }
+
+   #[test]
+   fn save_and_restore() {
+      let client = client();
+      let session = Session::from_cookie_header("auth_token=abc; ct0=def")
+         .with_bearer_token("bearer")
+         .with_transaction(client.clone());
+
+      let restored = session.to_string().parse::<Session>().unwrap();
+      assert_eq!(restored.cookie_header(), session.cookie_header());
+      assert_eq!(restored.bearer_token(), Some("bearer"));
+
+      let transaction = restored.transaction().unwrap();
+      assert_eq!(transaction.key_bytes(), client.key_bytes());
+      assert_eq!(transaction.ondemand_hash(), client.ondemand_hash());
+      assert_eq!(transaction.home_page_hash(), client.home_page_hash());
+      assert_eq!(transaction.key_fingerprint(), client.key_fingerprint());
+      assert!(transaction.created_at() <= client.created_at());
+      assert!(
+         client
+            .created_at()
+            .duration_since(transaction.created_at())
+            .unwrap()
+            < Duration::from_secs(1)
+      );
+      assert_eq!(
+         transaction.generate_transaction_id_at("GET", "/path", 1_000),
+         client.generate_transaction_id_at("GET", "/path", 1_000)
+      );
+
+      assert!(!transaction.is_clock_synced());
+      client.set_clock_offset(-42);
+      let restored = session.to_string().parse::<Session>().unwrap();
+      let transaction = restored.transaction().unwrap();
+      assert!(transaction.is_clock_synced());
+      assert_eq!(transaction.clock_offset(), -42);
+
+      let empty = Session::default().to_string().parse::<Session>().unwrap();
+      assert!(empty.transaction().is_none());
+      "key_bytes = AAAA\n".parse::<Session>().unwrap_err();
+      "nonsense".parse::<Session>().unwrap_err();
+      "version = 9".parse::<Session>().unwrap_err();
+      "clock_offset = soon".parse::<Session>().unwrap_err();
+      format!("created_at = {}", u64::MAX)
+         .parse::<Session>()
+         .unwrap_err();
+   }
+
+   #[test]
+   fn custom_version_round_trip() {
+      /// V3 under another version byte.
+      struct V4;
+
+      impl TransactionAlgorithm for V4 {
+         fn version(&self) -> u8 {
+            4
+         }
+
+         fn detect(&self, ondemand_js: &str) -> bool {
+            ondemand_js.contains("/* v4 */")
+         }
+
+         fn animation_key(
+            &self,
+            key_bytes: &[u8],
+            indices: &OndemandIndices,
+            frames: &[Vec<Vec<i32>>],
+         ) -> Result<String, Error> {
+            V3.animation_key(key_bytes, indices, frames)
+         }
+
+         fn generate(
+            &self,
+            key_bytes: &[u8],
+            animation_key: &str,
+            method: &str,
+            path: &str,
+            time: u32,
+         ) -> String {
+            V3.generate(key_bytes, animation_key, method, path, time)
+         }
+      }
+
+      let mut registry = Registry::default();
+      registry.register(Arc::new(V4));
+      let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>());
+      let js = format!("/* v4 */{}", pages.ondemand_js());
+      let client =
+         ClientTransaction::new_with_registry(&pages.home_page_html(), &js, &registry).unwrap();
+      let saved = Session::default().with_transaction(client).to_string();
+
+      saved.parse::<Session>().unwrap_err();
+      let restored = Session::parse_with_registry(&saved, &registry).unwrap();
+      assert_eq!(restored.transaction().unwrap().algorithm().version(), 4);
+   }
+}
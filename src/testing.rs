@@ -0,0 +1,274 @@
+//! Synthetic X pages for end-to-end tests.
+//!
+//! Builds a homepage and an ondemand.js from chosen key material instead of
+//! scraped content, so
+//! [`ClientTransaction::new`](crate::ClientTransaction::new) can be exercised
+//! without saving real X pages.
+//!
+//! ```
+//! use xitter_txid::{
+//!    ClientTransaction,
+//!    testing::SyntheticPages,
+//! };
+//!
+//! let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>()).with_indices(2, vec![12, 14, 7]);
+//! let client = ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap();
+//! assert_eq!(client.row_index(), 2);
+//! ```
+
+use std::{
+   fmt::Write as _,
+   iter,
+};
+
+use crate::utils::base64_encode;
+
+/// Number of `loading-x-anim-N` SVGs on the homepage.
+pub const FRAME_COUNT: usize = 4;
+
+/// Number of rows in each frame's coordinate table.
+pub const ROWS_PER_FRAME: usize = 16;
+
+/// Number of values in each row of a coordinate table.
+pub const VALUES_PER_ROW: usize = 11;
+
+/// Bearer token embedded in [`main_js`], shaped like X's.
+pub const BEARER_TOKEN: &str = "AAAAAAAAAAAAAAAAAAAAAFsYnTHeSyNtHeTiCbEaReR0000%\
+                                3DsYnThEtIcToKeNnOtReAlLyUsAbLeAnYwHeRe0123456789";
+
+/// A homepage and ondemand.js generated from known key material.
+#[derive(Debug, Clone)]
+pub struct SyntheticPages {
+   key_bytes:        Vec<u8>,
+   frames:           Vec<Vec<Vec<i32>>>,
+   row_index:        usize,
+   key_byte_indices: Vec<usize>,
+   ondemand_hash:    String,
+   main_url:         Option<String>,
+}
+
+impl SyntheticPages {
+   /// Creates pages for the given verification key bytes, with
+   /// [`default_frames`] and indices `2` and `[12, 14, 7]`.
+   pub fn new(key_bytes: impl Into<Vec<u8>>) -> Self {
+      Self {
+         key_bytes:        key_bytes.into(),
+         frames:           default_frames(),
+         row_index:        2,
+         key_byte_indices: vec![12, 14, 7],
+         ondemand_hash:    "5a1b2c3".to_owned(),
+         main_url:         None,
+      }
+   }
+
+   /// Replaces the coordinate tables, one per `loading-x-anim-N` SVG.
+   #[must_use]
+   pub fn with_frames(mut self, frames: Vec<Vec<Vec<i32>>>) -> Self {
+      self.frames = frames;
+      self
+   }
+
+   /// Replaces the row index and the key byte indices in ondemand.js.
+   #[must_use]
+   pub fn with_indices(mut self, row_index: usize, key_byte_indices: Vec<usize>) -> Self {
+      self.row_index = row_index;
+      self.key_byte_indices = key_byte_indices;
+      self
+   }
+
+   /// Replaces the ondemand chunk hash in the homepage's chunk map.
+   #[must_use]
+   pub fn with_ondemand_hash(mut self, hash: impl Into<String>) -> Self {
+      self.ondemand_hash = hash.into();
+      self
+   }
+
+   /// Adds a `<script src>` tag loading main.js from `url`.
+   #[must_use]
+   pub fn with_main_url(mut self, url: impl Into<String>) -> Self {
+      self.main_url = Some(url.into());
+      self
+   }
+
+   /// Verification key bytes the homepage embeds, base64-encoded.
+   #[must_use]
+   pub fn key_bytes(&self) -> &[u8] {
+      &self.key_bytes
+   }
+
+   /// Coordinate tables of the `loading-x-anim-N` SVGs.
+   #[must_use]
+   pub fn frames(&self) -> &[Vec<Vec<i32>>] {
+      &self.frames
+   }
+
+   /// Row index listed first in ondemand.js.
+   #[must_use]
+   pub const fn row_index(&self) -> usize {
+      self.row_index
+   }
+
+   /// Key byte indices listed after the row index in ondemand.js.
+   #[must_use]
+   pub fn key_byte_indices(&self) -> &[usize] {
+      &self.key_byte_indices
+   }
+
+   /// Ondemand chunk hash in the homepage's chunk map.
+   #[must_use]
+   pub fn ondemand_hash(&self) -> &str {
+      &self.ondemand_hash
+   }
+
+   /// Renders the homepage: verification meta tag, webpack chunk map and one
+   /// `loading-x-anim-N` SVG per frame.
+   #[must_use]
+   pub fn home_page_html(&self) -> String {
+      let mut html = String::from(
+         "<!DOCTYPE html><html dir=\"ltr\" lang=\"en\"><head><meta charset=\"utf-8\" />",
+      );
+
+      let _ = write!(
+         html,
+         "<meta name=\"twitter-site-verification\" content=\"{}\" />",
+         base64_encode(&self.key_bytes)
+      );
+      html.push_str("<title>X</title>");
+      if let Some(ref url) = self.main_url {
+         let _ = write!(
+            html,
+            "<script type=\"text/javascript\" crossorigin=\"anonymous\" src=\"{url}\"></script>"
+         );
+      }
+      html.push_str("<script nonce=\"synthetic\">window.__CHUNKS__={");
+      let _ = write!(
+         html,
+         "\"vendor\":\"3f2a1c9\",\"ondemand.s\":\"{}\",\"loader.AppModules\":\"52b6e0f\"",
+         self.ondemand_hash
+      );
+      html.push_str("};</script></head><body><div id=\"react-root\"></div>");
+
+      for (index, frame) in self.frames.iter().enumerate() {
+         let _ = write!(
+            html,
+            "<svg id=\"loading-x-anim-{index}\" aria-hidden=\"true\" viewBox=\"0 0 1 1\"><g><path \
+             d=\"M 0,0 L 1,1 z\" fill=\"none\"/><path d=\"{}\"/></g></svg>",
+            frame_path(frame)
+         );
+      }
+
+      html.push_str("</body></html>\n");
+      html
+   }
+
+   /// Renders an ondemand.js whose `(e[N], 16)` calls list the row index
+   /// first, then the key byte indices.
+   #[must_use]
+   pub fn ondemand_js(&self) -> String {
+      let calls = iter::once(self.row_index)
+         .chain(self.key_byte_indices.iter().copied())
+         .map(|index| format!("parseInt(e[{index}], 16)"))
+         .collect::<Vec<_>>()
+         .join(",");
+
+      format!(
+         "\"use strict\";(self.webpackChunk_twitter_responsive_web=self.\
+          webpackChunk_twitter_responsive_web||[]).push([[\"ondemand.s\"],{{84129:(t,n,r)=>{{r.\
+          d(n,{{default:()=>o}});function o(e){{return[{calls}].map(t=>t%16)}}}}}}]);\n"
+      )
+   }
+}
+
+/// Renders a main.js holding [`BEARER_TOKEN`] and a `UserByScreenName`
+/// GraphQL operation.
+#[must_use]
+pub fn main_js() -> String {
+   let operation = "{queryId:\"sYnThEtIc-QuErYiD\",operationName:\"UserByScreenName\",\
+                    operationType:\"query\",metadata:{featureSwitches:[\"\
+                    hidden_profile_subscriptions_enabled\"],fieldToggles:[]}}";
+
+   format!(
+      "(self.webpackChunk_twitter_responsive_web=self.webpackChunk_twitter_responsive_web||[]).\
+       push([[\"main\"],{{1:(e,t,n)=>{{const \
+       o=\"{BEARER_TOKEN}\";}},2:e=>{{e.exports={operation}}}}}]);\n"
+   )
+}
+
+/// Renders a coordinate table as an SVG path `d` attribute.
+///
+/// The leading `M 10,30 ` move command is skipped by the parser, and each row
+/// becomes one `C` segment.
+#[must_use]
+pub fn frame_path(rows: &[Vec<i32>]) -> String {
+   let mut path = String::from("M 10,30 ");
+
+   for row in rows {
+      path.push('C');
+      let values = row.iter().map(ToString::to_string).collect::<Vec<_>>();
+      path.push_str(&values.join(" "));
+   }
+
+   path
+}
+
+/// Deterministic coordinate tables with values in `0..=255`, shaped like the
+/// ones on X's homepage.
+#[must_use]
+pub fn default_frames() -> Vec<Vec<Vec<i32>>> {
+   let mut state = 0x2545_F491_u32;
+   let mut next = move || {
+      state ^= state << 13;
+      state ^= state >> 17;
+      state ^= state << 5;
+      i32::from((state >> 24) as u8)
+   };
+
+   let mut row = move || iter::repeat_with(&mut next).take(VALUES_PER_ROW).collect();
+   let mut frame = move || iter::repeat_with(&mut row).take(ROWS_PER_FRAME).collect();
+   iter::repeat_with(&mut frame).take(FRAME_COUNT).collect()
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn default_frames_shape() {
+      let frames = default_frames();
+      assert_eq!(frames.len(), FRAME_COUNT);
+      for frame in &frames {
+         assert_eq!(frame.len(), ROWS_PER_FRAME);
+         for row in frame {
+            assert_eq!(row.len(), VALUES_PER_ROW);
+            assert!(row.iter().all(|value| (0..=255).contains(value)));
+         }
+      }
+   }
+
+   #[test]
+   fn frame_path_format() {
+      let path = frame_path(&[vec![1, 2, 3], vec![4, 5, 6]]);
+      assert_eq!(path, "M 10,30 C1 2 3C4 5 6");
+   }
+
+   #[test]
+   fn home_page_contents() {
+      let pages = SyntheticPages::new([0_u8; 48]).with_ondemand_hash("abc123");
+      let html = pages.home_page_html();
+      assert!(html.contains("name=\"twitter-site-verification\""));
+      assert!(html.contains("\"ondemand.s\":\"abc123\""));
+      for index in 0..FRAME_COUNT {
+         assert!(html.contains(&format!("id=\"loading-x-anim-{index}\"")));
+      }
+   }
+
+   #[test]
+   fn ondemand_js_contents() {
+      let pages = SyntheticPages::new([0_u8; 48]).with_indices(3, vec![9, 40]);
+      let js = pages.ondemand_js();
+      let first = js.find("(e[3], 16)").unwrap();
+      let second = js.find("(e[9], 16)").unwrap();
+      let third = js.find("(e[40], 16)").unwrap();
+      assert!(first < second && second < third);
+   }
+}
@@ -0,0 +1,164 @@
+//! Times as transaction IDs encode them.
+
+use std::time::{
+   Duration,
+   SystemTime,
+   UNIX_EPOCH,
+};
+
+use crate::error::Error;
+
+/// X's custom epoch: 2023-05-01 07:00:00 UTC, in seconds since the Unix
+/// epoch.
+const X_EPOCH: u64 = 1_682_924_400;
+
+/// Seconds since X's epoch (2023-05-01 07:00:00 UTC), the time a
+/// transaction ID carries.
+///
+/// IDs store it as a `u32`, so only times from the epoch until
+/// 2159-06-07 13:28:15 UTC can be represented. Converting a [`SystemTime`]
+/// outside that range fails rather than producing an ID that looks valid but
+/// isn't.
+///
+/// ```ignore
+/// let time = XTimestamp::try_from(SystemTime::now())?;
+/// let id = client.generate_transaction_id_at("GET", path, time.as_secs());
+/// assert_eq!(DecodedTransactionId::decode(&id)?.timestamp(), time);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct XTimestamp(u32);
+
+impl XTimestamp {
+   /// X's epoch.
+   pub const EPOCH: Self = Self(0);
+   /// The last representable second, 2159-06-07 13:28:15 UTC.
+   pub const MAX: Self = Self(u32::MAX);
+
+   #[must_use]
+   pub const fn from_secs(secs: u32) -> Self {
+      Self(secs)
+   }
+
+   /// The current local time.
+   pub fn now() -> Result<Self, Error> {
+      SystemTime::now().try_into()
+   }
+
+   #[must_use]
+   pub const fn as_secs(self) -> u32 {
+      self.0
+   }
+
+   #[must_use]
+   pub fn to_system_time(self) -> SystemTime {
+      UNIX_EPOCH + Duration::from_secs(X_EPOCH + u64::from(self.0))
+   }
+
+   /// Like [`try_from`](TryFrom::try_from), clamping times outside the
+   /// range to [`EPOCH`](Self::EPOCH) or [`MAX`](Self::MAX).
+   #[must_use]
+   pub fn saturating_from(time: SystemTime) -> Self {
+      match Self::try_from(time) {
+         Ok(timestamp) => timestamp,
+         Err(Error::TimestampOverflow(_)) => Self::MAX,
+         Err(_) => Self::EPOCH,
+      }
+   }
+}
+
+impl TryFrom<SystemTime> for XTimestamp {
+   type Error = Error;
+
+   fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+      let epoch = UNIX_EPOCH + Duration::from_secs(X_EPOCH);
+      let secs = time
+         .duration_since(epoch)
+         .map_err(|err| Error::BeforeEpoch(err.duration()))?
+         .as_secs();
+      u32::try_from(secs)
+         .map(Self)
+         .map_err(|_| Error::TimestampOverflow(secs))
+   }
+}
+
+impl From<XTimestamp> for SystemTime {
+   fn from(timestamp: XTimestamp) -> Self {
+      timestamp.to_system_time()
+   }
+}
+
+impl From<u32> for XTimestamp {
+   fn from(secs: u32) -> Self {
+      Self(secs)
+   }
+}
+
+impl From<XTimestamp> for u32 {
+   fn from(timestamp: XTimestamp) -> Self {
+      timestamp.0
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::utils::parse_http_date;
+
+   #[test]
+   fn range_bounds() {
+      assert_eq!(
+         XTimestamp::EPOCH
+            .to_system_time()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+         1_682_924_400
+      );
+      assert_eq!(
+         Some(XTimestamp::EPOCH.to_system_time()),
+         parse_http_date("Mon, 01 May 2023 07:00:00 GMT")
+      );
+      assert_eq!(
+         Some(XTimestamp::MAX.to_system_time()),
+         parse_http_date("Thu, 07 Jun 2159 13:28:15 GMT")
+      );
+   }
+
+   #[test]
+   fn system_time_round_trip() {
+      let may_2023 = UNIX_EPOCH + Duration::from_secs(X_EPOCH);
+      assert_eq!(XTimestamp::try_from(may_2023).unwrap(), XTimestamp::EPOCH);
+
+      let later = may_2023 + Duration::from_millis(1_500);
+      let timestamp = XTimestamp::try_from(later).unwrap();
+      assert_eq!(timestamp.as_secs(), 1);
+      assert_eq!(
+         SystemTime::from(timestamp),
+         may_2023 + Duration::from_secs(1)
+      );
+
+      assert_eq!(
+         XTimestamp::try_from(XTimestamp::MAX.to_system_time()).unwrap(),
+         XTimestamp::MAX
+      );
+   }
+
+   #[test]
+   fn out_of_range() {
+      let may_2023 = UNIX_EPOCH + Duration::from_secs(X_EPOCH);
+
+      let before = may_2023 - Duration::from_mins(1);
+      match XTimestamp::try_from(before) {
+         Err(Error::BeforeEpoch(early)) => assert_eq!(early, Duration::from_mins(1)),
+         other => panic!("unexpected {other:?}"),
+      }
+      assert_eq!(XTimestamp::saturating_from(before), XTimestamp::EPOCH);
+
+      let after = XTimestamp::MAX.to_system_time() + Duration::from_secs(1);
+      match XTimestamp::try_from(after) {
+         Err(Error::TimestampOverflow(secs)) => assert_eq!(secs, u64::from(u32::MAX) + 1),
+         other => panic!("unexpected {other:?}"),
+      }
+      assert_eq!(XTimestamp::saturating_from(after), XTimestamp::MAX);
+   }
+}
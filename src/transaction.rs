@@ -1,55 +1,133 @@
 //! Transaction ID generation for X (Twitter) API requests.
 
 use std::{
-   iter,
+   fmt,
+   sync::{
+      Arc,
+      atomic::{
+         AtomicBool,
+         AtomicI64,
+         Ordering,
+      },
+   },
    time::{
+      Duration,
       SystemTime,
-      UNIX_EPOCH,
    },
 };
 
+use data_encoding::HEXLOWER;
 use hmac_sha256::Hash;
 
+#[cfg(feature = "fetch")]
+use crate::fetch::{
+   FetchOptions,
+   Page,
+};
 use crate::{
-   cubic_curve::Cubic,
+   algorithm::{
+      RandomSource,
+      Registry,
+      TransactionAlgorithm,
+      V3,
+   },
+   animation::FrameSelection,
+   discovery,
    error::Error,
-   interpolate::interpolate,
-   rotation::rotation_matrix,
+   ondemand::OndemandIndices,
+   parse,
+   timestamp::XTimestamp,
    utils::{
-      base64_decode,
-      base64_encode,
-      float_to_hex,
       js_round,
-      odd_coefficient,
+      parse_http_date,
    },
 };
 
-#[cfg(feature = "fetch")]
-const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, \
-                          like Gecko) Chrome/133.0.0.0 Safari/537.36";
-
-const ONDEMAND_BASE_URL: &str = "https://abs.twimg.com/responsive-web/client-web";
+/// Where the ondemand.s chunk is served from; `{hash}` is replaced by the hash
+/// from the homepage.
+pub const ONDEMAND_URL_TEMPLATE: &str =
+   "https://abs.twimg.com/responsive-web/client-web/ondemand.s.{hash}a.js";
+
+/// Fills in the `{hash}` placeholder of an ondemand URL template.
+#[expect(
+   clippy::literal_string_with_formatting_args,
+   reason = "`{hash}` is a URL template placeholder"
+)]
+pub fn ondemand_url(template: &str, hash: &str) -> String {
+   template.replace("{hash}", hash)
+}
 
 /// Secret salt from X's client-side JavaScript.
-const HASH_SALT: &str = "obfiowerehiring";
+pub const HASH_SALT: &str = "obfiowerehiring";
+
+/// Version byte appended to every transaction ID.
+pub const PROTOCOL_VERSION: u8 = 3;
 
-/// X's custom epoch: 2023-05-01 00:00:00 UTC.
-const X_EPOCH: u64 = 1_682_924_400;
+/// Number of hash bytes embedded in a transaction ID.
+pub const HASH_BYTES: usize = 16;
 
 const TOTAL_ANIMATION_TIME: f64 = 4096.0;
 const FRAME_COUNT: u8 = 4;
 const ROW_INDEX_MODULUS: u8 = 16;
 const FRAME_SELECTOR_INDEX: usize = 5;
-const MIN_FRAME_VALUES: usize = 11;
 
 /// Client for generating X (Twitter) transaction IDs.
 ///
 /// Holds cryptographic material extracted from X's homepage and JavaScript
 /// files. Create one with [`fetch`](Self::fetch) (requires `fetch` feature)
 /// or [`new`](Self::new).
+///
+/// Also records where its key material came from, so callers can tell when
+/// to refresh it; see [`is_stale`](Self::is_stale). `Debug` output shows
+/// this metadata and a [fingerprint](Self::key_fingerprint) of the key, but
+/// not the key material itself.
+///
+/// IDs embed the current time, so a client whose clock is off gets them
+/// rejected. Clients built by [`fetch`](Self::fetch) correct for this with
+/// the `Date` header of the homepage, or else of ondemand.js, compared with
+/// when that page was fetched, so copies from the
+/// [cache](crate::FileCache) count too. If neither page had one, the clock
+/// is left alone; check [`is_clock_synced`](Self::is_clock_synced), and see
+/// [`sync_clock`](Self::sync_clock).
+#[derive(Clone)]
 pub struct ClientTransaction {
-   key_bytes:     Vec<u8>,
-   animation_key: String,
+   key_bytes:      Vec<u8>,
+   animation_key:  String,
+   indices:        OndemandIndices,
+   ondemand_hash:  Option<String>,
+   home_page_hash: Option<String>,
+   created_at:     SystemTime,
+   algorithm:      Arc<dyn TransactionAlgorithm>,
+   /// Shared between clones.
+   clock_offset:   Arc<ClockOffset>,
+   random_source:  RandomSource,
+}
+
+/// When a client's key material is due for a refresh.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StalePolicy {
+   /// Stale once the key material is older than this.
+   pub max_age:       Duration,
+   /// Stale if set and different from the client's ondemand.s hash, e.g.
+   /// the hash a newer homepage points at.
+   pub ondemand_hash: Option<String>,
+}
+
+/// Seconds the server's clock is ahead of ours, and whether that was ever
+/// set.
+#[derive(Debug, Default)]
+struct ClockOffset {
+   seconds: AtomicI64,
+   synced:  AtomicBool,
+}
+
+impl Default for StalePolicy {
+   fn default() -> Self {
+      Self {
+         max_age:       Duration::from_mins(30),
+         ondemand_hash: None,
+      }
+   }
 }
 
 impl ClientTransaction {
@@ -61,27 +139,92 @@ impl ClientTransaction {
    /// ```
    #[cfg(feature = "fetch")]
    pub fn fetch() -> Result<Self, Error> {
-      let home_response = minreq::get("https://x.com")
-         .with_header("User-Agent", USER_AGENT)
-         .send()?;
+      Self::fetch_with(&FetchOptions::default())
+   }
 
-      if home_response.status_code != 200 {
-         return Err(Error::HttpStatus(home_response.status_code, "x.com"));
-      }
+   /// Like [`fetch`](Self::fetch), with custom headers, cookies, proxy,
+   /// timeout and URLs.
+   ///
+   /// ```ignore
+   /// let options = FetchOptions {
+   ///    cookies: Some("auth_token=...; ct0=...".into()),
+   ///    timeout: Some(Duration::from_secs(10)),
+   ///    ..FetchOptions::default()
+   /// };
+   /// let client = ClientTransaction::fetch_with(&options)?;
+   /// ```
+   #[cfg(feature = "fetch")]
+   pub fn fetch_with(options: &FetchOptions) -> Result<Self, Error> {
+      let home = options.get_home()?;
+      let (hash, ondemand) = options.get_ondemand(&home.body)?;
+      Self::from_fetched(&home, hash, &ondemand, &options.registry)
+   }
 
-      let home_html = home_response.as_str()?.to_owned();
-      let ondemand_url = Self::extract_ondemand_url(&home_html)?;
+   /// Creates a fresh client with the default [`FetchOptions`], fetching
+   /// ondemand.js only if its hash changed since this client was created.
+   #[cfg(feature = "fetch")]
+   pub fn refresh(&self) -> Result<Self, Error> {
+      self.refresh_with(&FetchOptions::default())
+   }
 
-      let js_response = minreq::get(&ondemand_url)
-         .with_header("User-Agent", USER_AGENT)
-         .send()?;
+   /// Like [`refresh`](Self::refresh), with custom options. The
+   /// [`random_source`](Self::random_source) is carried over, and so is a
+   /// [synced](Self::is_clock_synced) clock offset unless a fetched page has
+   /// a `Date` header.
+   #[cfg(feature = "fetch")]
+   pub fn refresh_with(&self, options: &FetchOptions) -> Result<Self, Error> {
+      let home = options.get_home()?;
+      let client = if let Some(indices) = self.reusable_indices(&home.body) {
+         Self::from_home_page(&home.body, indices.clone(), Arc::clone(&self.algorithm))?
+            .with_created_at(home.fetched_at)
+            .with_server_date(&home)
+      } else {
+         let (hash, ondemand) = options.get_ondemand(&home.body)?;
+         let registry = options.registry.with_fallback(Arc::clone(&self.algorithm));
+         Self::from_fetched(&home, hash, &ondemand, &registry)?
+      };
 
-      if js_response.status_code != 200 {
-         return Err(Error::HttpStatus(js_response.status_code, "ondemand.js"));
+      if self.is_clock_synced() && !client.is_clock_synced() {
+         client.set_clock_offset(self.clock_offset());
       }
+      Ok(client.with_random_source(self.random_source.clone()))
+   }
+
+   /// Creates a client from a fetched homepage and the ondemand.s chunk with
+   /// hash `ondemand_hash`. It is dated to when the homepage was fetched and
+   /// its clock synced with the pages' `Date` headers, preferring the
+   /// homepage's.
+   #[cfg(feature = "fetch")]
+   pub(crate) fn from_fetched(
+      home: &Page,
+      ondemand_hash: Option<String>,
+      ondemand: &Page,
+      registry: &Registry,
+   ) -> Result<Self, Error> {
+      let mut client = Self::new_with_registry(&home.body, &ondemand.body, registry)?
+         .with_created_at(home.fetched_at)
+         .with_server_date(ondemand)
+         .with_server_date(home);
+      client.ondemand_hash = ondemand_hash;
+      Ok(client)
+   }
+
+   /// Dates the key material to when its homepage was fetched, which for a
+   /// cached copy is earlier than now.
+   #[cfg(feature = "fetch")]
+   const fn with_created_at(mut self, created_at: SystemTime) -> Self {
+      self.created_at = created_at;
+      self
+   }
 
-      let ondemand_js = js_response.as_str()?.to_owned();
-      Self::new(&home_html, &ondemand_js)
+   /// Syncs the clock with a fetched page's `Date` header, if it has a
+   /// valid one, as of when the page was fetched.
+   #[cfg(feature = "fetch")]
+   fn with_server_date(self, page: &Page) -> Self {
+      if let Some(server_time) = page.date.as_deref().and_then(parse_http_date) {
+         self.sync_clock_to(server_time, page.fetched_at);
+      }
+      self
    }
 
    /// Creates a client from pre-fetched HTML and JavaScript.
@@ -89,352 +232,460 @@ impl ClientTransaction {
    /// Use this if you want to bring your own HTTP client.
    /// Get the JS URL with [`extract_ondemand_url`](Self::extract_ondemand_url).
    pub fn new(home_page_html: &str, ondemand_js: &str) -> Result<Self, Error> {
-      let (row_index, key_bytes_indices) = Self::parse_indices(ondemand_js)?;
-      let key = Self::verification_key(home_page_html)?;
-      let key_bytes = Self::decode_key(&key)?;
-      let animation_key =
-         Self::compute_animation_key(&key_bytes, home_page_html, row_index, &key_bytes_indices)?;
+      Self::new_with_registry(home_page_html, ondemand_js, &Registry::default())
+   }
+
+   /// Like [`new`](Self::new), with the algorithm version `registry`
+   /// [detects](Registry::detect) in ondemand.js. Fails if none matches.
+   pub fn new_with_registry(
+      home_page_html: &str,
+      ondemand_js: &str,
+      registry: &Registry,
+   ) -> Result<Self, Error> {
+      let algorithm = Self::detect_algorithm(ondemand_js, registry)?;
+      Self::from_home_page(home_page_html, algorithm.indices(ondemand_js)?, algorithm)
+   }
+
+   fn detect_algorithm(
+      ondemand_js: &str,
+      registry: &Registry,
+   ) -> Result<Arc<dyn TransactionAlgorithm>, Error> {
+      registry.detect(ondemand_js).ok_or_else(|| {
+         Error::MissingKey(format!(
+            "ondemand.js of a known algorithm version (known: {:?})",
+            registry.versions().collect::<Vec<_>>()
+         ))
+      })
+   }
+
+   /// Creates a client from homepage HTML and indices parsed from an earlier
+   /// ondemand.js.
+   ///
+   /// Which chunk the indices came from is unknown, so the client has no
+   /// [`ondemand_hash`](Self::ondemand_hash) and
+   /// [`reusable_indices`](Self::reusable_indices) never reuses them.
+   pub fn with_indices(home_page_html: &str, indices: OndemandIndices) -> Result<Self, Error> {
+      Ok(Self {
+         ondemand_hash: None,
+         ..Self::from_home_page(home_page_html, indices, Arc::new(V3))?
+      })
+   }
+
+   fn from_home_page(
+      home_page_html: &str,
+      indices: OndemandIndices,
+      algorithm: Arc<dyn TransactionAlgorithm>,
+   ) -> Result<Self, Error> {
+      let key_bytes = parse::decode_verification_key(&parse::verification_key(home_page_html)?)?;
+      let frames = parse::animation_frames(home_page_html);
+
+      Ok(Self {
+         ondemand_hash: Self::extract_ondemand_hash(home_page_html).ok(),
+         home_page_hash: Some(fingerprint(home_page_html.as_bytes())),
+         ..Self::from_parts_with(algorithm, key_bytes, indices, &frames)?
+      })
+   }
+
+   /// Creates a client from key material obtained elsewhere: the decoded
+   /// verification key, the ondemand.js indices and the coordinate table of
+   /// every `loading-x-anim-N` frame.
+   ///
+   /// Each piece has a parser in [`parse`](crate::parse), so cached pieces
+   /// can be combined with freshly fetched ones.
+   ///
+   /// ```ignore
+   /// let key_bytes = parse::decode_verification_key(&parse::verification_key(&html)?)?;
+   /// let frames = parse::animation_frames(&html);
+   /// let client = ClientTransaction::from_parts(key_bytes, cached_indices, &frames)?;
+   /// ```
+   pub fn from_parts(
+      key_bytes: impl Into<Vec<u8>>,
+      indices: OndemandIndices,
+      frames: &[Vec<Vec<i32>>],
+   ) -> Result<Self, Error> {
+      Self::from_parts_with(Arc::new(V3), key_bytes, indices, frames)
+   }
+
+   /// Like [`from_parts`](Self::from_parts), deriving the animation key with
+   /// `algorithm` instead of [`V3`].
+   pub fn from_parts_with(
+      algorithm: Arc<dyn TransactionAlgorithm>,
+      key_bytes: impl Into<Vec<u8>>,
+      indices: OndemandIndices,
+      frames: &[Vec<Vec<i32>>],
+   ) -> Result<Self, Error> {
+      let key_bytes = key_bytes.into();
+      let animation_key = algorithm.animation_key(&key_bytes, &indices, frames)?;
 
       Ok(Self {
          key_bytes,
          animation_key,
+         indices,
+         ondemand_hash: None,
+         home_page_hash: None,
+         created_at: SystemTime::now(),
+         algorithm,
+         clock_offset: Arc::default(),
+         random_source: RandomSource::default(),
       })
    }
 
-   /// Extracts the ondemand.s.*.js URL from homepage HTML.
-   pub fn extract_ondemand_url(home_page_html: &str) -> Result<String, Error> {
-      let markers = ["\"ondemand.s\"", "'ondemand.s'"];
-
-      for marker in markers {
-         if let Some(pos) = home_page_html.find(marker) {
-            let Some(after_marker) = home_page_html.get(pos + marker.len()..) else {
-               continue;
-            };
-
-            let after_colon = after_marker
-               .trim_start()
-               .strip_prefix(':')
-               .map(str::trim_start);
-
-            if let Some(rest) = after_colon {
-               let (quote, rest) = if let Some(stripped) = rest.strip_prefix('"') {
-                  ('"', stripped)
-               } else if let Some(stripped) = rest.strip_prefix('\'') {
-                  ('\'', stripped)
-               } else {
-                  continue;
-               };
-
-               if let Some(end) = rest.find(quote) {
-                  let hash = &rest[..end];
-                  if !hash.is_empty() && hash.chars().all(char::is_alphanumeric) {
-                     return Ok(format!("{ONDEMAND_BASE_URL}/ondemand.s.{hash}a.js"));
-                  }
-               }
-            }
-         }
+   /// Like [`from_parts`](Self::from_parts), with the verification key as it
+   /// appears on the homepage (base64).
+   pub fn from_base64_parts(
+      verification_key: &str,
+      indices: OndemandIndices,
+      frames: &[Vec<Vec<i32>>],
+   ) -> Result<Self, Error> {
+      Self::from_parts(
+         parse::decode_verification_key(verification_key)?,
+         indices,
+         frames,
+      )
+   }
+
+   /// Recreates a client from the material of an earlier one, as returned by
+   /// [`key_bytes`](Self::key_bytes), [`animation_key`](Self::animation_key),
+   /// [`indices`](Self::indices) and [`ondemand_hash`](Self::ondemand_hash),
+   /// e.g. to restore a saved [`Session`](crate::Session).
+   #[must_use]
+   pub fn from_key_material(
+      key_bytes: impl Into<Vec<u8>>,
+      animation_key: impl Into<String>,
+      indices: OndemandIndices,
+      ondemand_hash: Option<String>,
+   ) -> Self {
+      Self {
+         key_bytes: key_bytes.into(),
+         animation_key: animation_key.into(),
+         indices,
+         ondemand_hash,
+         home_page_hash: None,
+         created_at: SystemTime::now(),
+         algorithm: Arc::new(V3),
+         clock_offset: Arc::default(),
+         random_source: RandomSource::default(),
       }
+   }
 
-      // Provide helpful context about what we received
-      let hint = if home_page_html.contains("login") || home_page_html.contains("LoginForm") {
-         " (received login page - may need cookies)"
-      } else if home_page_html.len() < 10000 {
-         " (response too small - may be rate limited or blocked)"
-      } else {
-         " (X may have changed their page structure)"
-      };
-      Err(Error::MissingKey(format!("ondemand file hash{hint}")))
+   /// Generates IDs with `algorithm` instead of [`V3`]. The animation key is
+   /// kept, so this is for restoring material `algorithm` derived, e.g. with
+   /// [`from_key_material`](Self::from_key_material).
+   #[must_use]
+   pub fn with_algorithm(mut self, algorithm: Arc<dyn TransactionAlgorithm>) -> Self {
+      self.algorithm = algorithm;
+      self
    }
 
-   /// Generates a transaction ID for an API request.
+   /// Takes the random byte of each ID from `random_source` instead of the
+   /// request hash.
+   ///
+   /// ```ignore
+   /// let client = client.with_random_source(RandomSource::Os);
+   /// ```
    #[must_use]
-   pub fn generate_transaction_id(&self, method: &str, path: &str) -> String {
-      let time = Self::current_time();
-      let hash_input = format!("{method}!{path}!{time}{HASH_SALT}{}", self.animation_key);
-      let hash_result = Hash::hash(hash_input.as_bytes());
-
-      let time_bytes = time.to_le_bytes();
-      let random_byte = hash_result[16];
-
-      let mut bytes = Vec::with_capacity(self.key_bytes.len() + 4 + 16 + 1);
-      bytes.extend_from_slice(&self.key_bytes);
-      bytes.extend_from_slice(&time_bytes);
-      bytes.extend_from_slice(&hash_result[..16]);
-      bytes.push(3); // protocol version
-
-      let mut encoded = vec![random_byte];
-      encoded.extend(bytes.iter().map(|&byte| byte ^ random_byte));
-
-      base64_encode(&encoded).trim_end_matches('=').to_owned()
-   }
-
-   #[expect(
-      clippy::cast_possible_truncation,
-      reason = "timestamp fits in u32 until 2159"
-   )]
-   fn current_time() -> u32 {
-      SystemTime::now()
-         .duration_since(UNIX_EPOCH)
-         .map(|duration| duration.as_secs().saturating_sub(X_EPOCH) as u32)
-         .unwrap_or(0)
-   }
-
-   /// Finds (e[N], 16) patterns in the JS to get key byte indices.
-   fn parse_indices(ondemand_js: &str) -> Result<(usize, Vec<usize>), Error> {
-      let mut indices = Vec::new();
-      let bytes = ondemand_js.as_bytes();
-
-      for idx in 0..bytes.len().saturating_sub(10) {
-         if bytes.get(idx) == Some(&b'(') && bytes.get(idx + 2) == Some(&b'[') {
-            let start = idx + 3;
-            let mut end = start;
-            while end < bytes.len() && bytes.get(end).is_some_and(u8::is_ascii_digit) {
-               end += 1;
-            }
-
-            if end > start
-               && let Some(rest) = ondemand_js.get(end..)
-               && (rest.starts_with("], 16)") || rest.starts_with("],16)"))
-               && let Some(num_str) = ondemand_js.get(start..end)
-               && let Ok(num) = num_str.parse::<usize>()
-            {
-               indices.push(num);
-            }
-         }
-      }
+   pub const fn with_random_source(mut self, random_source: RandomSource) -> Self {
+      self.random_source = random_source;
+      self
+   }
 
-      if indices.is_empty() {
-         return Err(Error::MissingKey("key byte indices".into()));
-      }
+   /// Records when the key material was fetched and a
+   /// [fingerprint](Self::home_page_hash) of the homepage it came from, e.g.
+   /// when restoring a client saved earlier.
+   #[must_use]
+   pub fn with_provenance(
+      mut self,
+      created_at: SystemTime,
+      home_page_hash: Option<String>,
+   ) -> Self {
+      self.created_at = created_at;
+      self.home_page_hash = home_page_hash;
+      self
+   }
 
-      Ok((indices[0], indices[1..].to_vec()))
-   }
-
-   fn verification_key(html: &str) -> Result<String, Error> {
-      let marker = "name=\"twitter-site-verification\"";
-      let pos = html
-         .find(marker)
-         .ok_or_else(|| Error::MissingKey("twitter-site-verification meta tag".into()))?;
-
-      let tag_start = html
-         .get(..pos)
-         .and_then(|slice| slice.rfind('<'))
-         .unwrap_or(0);
-
-      let tag_end = html
-         .get(pos..)
-         .and_then(|slice| slice.find('>'))
-         .map_or(html.len(), |offset| pos + offset);
-
-      let tag = html
-         .get(tag_start..tag_end)
-         .ok_or_else(|| Error::Parse("malformed meta tag".into()))?;
-
-      let content_marker = "content=\"";
-      let content_pos = tag
-         .find(content_marker)
-         .ok_or_else(|| Error::MissingKey("content attribute".into()))?;
-
-      let value_start = content_pos + content_marker.len();
-      let value_end = tag
-         .get(value_start..)
-         .and_then(|slice| slice.find('"'))
-         .ok_or_else(|| Error::Parse("malformed content attribute".into()))?;
-
-      tag.get(value_start..value_start + value_end)
-         .map(ToOwned::to_owned)
-         .ok_or_else(|| Error::Parse("could not extract verification key".into()))
-   }
-
-   fn decode_key(key: &str) -> Result<Vec<u8>, Error> {
-      base64_decode(key).map_err(Error::from)
-   }
-
-   fn animation_frames(html: &str) -> Vec<String> {
-      let mut frames = Vec::new();
-      let mut search_pos = 0;
-
-      while let Some(pos) = html
-         .get(search_pos..)
-         .and_then(|slice| slice.find("id=\"loading-x-anim"))
-      {
-         let abs_pos = search_pos + pos;
-
-         let Some(svg_content) = html
-            .get(abs_pos..)
-            .and_then(|slice| slice.find("</svg>").map(|end| &slice[..end]))
-         else {
-            break;
-         };
-
-         let mut path_search = 0;
-         let mut found_curve_path = false;
-
-         while let Some(path_pos) = svg_content
-            .get(path_search..)
-            .and_then(|slice| slice.find("<path"))
-         {
-            let path_abs = path_search + path_pos;
-
-            let path_end = svg_content
-               .get(path_abs..)
-               .and_then(|slice| slice.find("/>").or_else(|| slice.find("></path>")));
-
-            if let Some(end_offset) = path_end
-               && let Some(path_tag) = svg_content.get(path_abs..path_abs + end_offset)
-               && let Some(d_value) = Self::extract_path_d(path_tag)
-               && d_value.contains('C')
-               && !found_curve_path
-            {
-               frames.push(d_value.to_owned());
-               found_curve_path = true;
-            }
-            path_search = path_abs + 5;
-         }
+   /// Returns this client's indices if `home_page_html` still points at the
+   /// ondemand.js they were parsed from, so a refresh can skip fetching it.
+   ///
+   /// ```ignore
+   /// let html = your_client.get("https://x.com")?.text()?;
+   /// let client = match client.reusable_indices(&html) {
+   ///    Some(indices) => ClientTransaction::with_indices(&html, indices.clone())?,
+   ///    None => ClientTransaction::new(&html, &fetch_ondemand_js(&html)?)?,
+   /// };
+   /// ```
+   #[must_use]
+   pub fn reusable_indices(&self, home_page_html: &str) -> Option<&OndemandIndices> {
+      let hash = Self::extract_ondemand_hash(home_page_html).ok()?;
+      (self.ondemand_hash.as_deref() == Some(hash.as_str())).then_some(&self.indices)
+   }
 
-         search_pos = abs_pos + svg_content.len();
-      }
+   /// Decoded bytes of the `twitter-site-verification` key.
+   #[must_use]
+   pub fn key_bytes(&self) -> &[u8] {
+      &self.key_bytes
+   }
 
-      frames
+   /// Animation key derived from the selected loading animation frame.
+   #[must_use]
+   pub fn animation_key(&self) -> &str {
+      &self.animation_key
    }
 
-   fn extract_path_d(path_tag: &str) -> Option<&str> {
-      let d_pos = path_tag.find(" d=\"")?;
-      let d_start = d_pos + 4;
-      let rest = path_tag.get(d_start..)?;
-      let d_end = rest.find('"')?;
-      rest.get(..d_end)
+   /// Index of the key byte that selects the animation row.
+   #[must_use]
+   pub const fn row_index(&self) -> usize {
+      self.indices.row_index()
    }
 
-   fn parse_path_to_coordinates(path_d: &str) -> Vec<Vec<i32>> {
-      // Skip initial move command ("M0 0 0 0" is 9 chars)
-      let d_content = path_d.get(9..).unwrap_or(path_d);
+   /// Indices of the key bytes that determine the animation time.
+   #[must_use]
+   pub fn key_byte_indices(&self) -> &[usize] {
+      self.indices.key_byte_indices()
+   }
 
-      d_content
-         .split('C')
-         .map(|segment| {
-            segment
-               .replace(|chr: char| !chr.is_ascii_digit() && chr != '-', " ")
-               .split_whitespace()
-               .filter_map(|token| token.parse::<i32>().ok())
-               .collect()
-         })
-         .collect()
+   /// Row and key byte indices parsed from ondemand.js.
+   #[must_use]
+   pub const fn indices(&self) -> &OndemandIndices {
+      &self.indices
    }
 
-   fn frame_data(key_bytes: &[u8], html: &str) -> Result<Vec<Vec<i32>>, Error> {
-      let frames = Self::animation_frames(html);
+   /// Hash of the ondemand.s chunk the indices were parsed from, if known.
+   #[must_use]
+   pub fn ondemand_hash(&self) -> Option<&str> {
+      self.ondemand_hash.as_deref()
+   }
 
-      if frames.is_empty() {
-         return Err(Error::MissingKey("animation frames".into()));
-      }
+   /// Where the random byte of each ID comes from.
+   #[must_use]
+   pub const fn random_source(&self) -> &RandomSource {
+      &self.random_source
+   }
 
-      let selector_byte = key_bytes
-         .get(FRAME_SELECTOR_INDEX)
-         .ok_or_else(|| Error::Parse("key too short for frame selection".into()))?;
+   /// Algorithm version the client generates IDs with.
+   #[must_use]
+   pub fn algorithm(&self) -> &dyn TransactionAlgorithm {
+      &*self.algorithm
+   }
 
-      let frame_index = usize::from(selector_byte % FRAME_COUNT);
-      let frame = frames
-         .get(frame_index)
-         .ok_or_else(|| Error::Parse("frame index out of bounds".into()))?;
+   /// When the key material was read from the homepage.
+   #[must_use]
+   pub const fn created_at(&self) -> SystemTime {
+      self.created_at
+   }
 
-      Ok(Self::parse_path_to_coordinates(frame))
+   /// Time since the key material was read from the homepage.
+   #[must_use]
+   pub fn age(&self) -> Duration {
+      self.created_at.elapsed().unwrap_or_default()
    }
 
-   fn solve(value: f64, min_val: f64, max_val: f64, rounding: bool) -> f64 {
-      let result = value.mul_add((max_val - min_val) / 255.0, min_val);
-      if rounding {
-         result.floor()
-      } else {
-         (result * 100.0).round() / 100.0
-      }
+   /// Fingerprint of the homepage HTML the key material was read from, if
+   /// the client was built from one.
+   #[must_use]
+   pub fn home_page_hash(&self) -> Option<&str> {
+      self.home_page_hash.as_deref()
    }
 
-   #[expect(
-      clippy::cast_possible_truncation,
-      reason = "color values are clamped to 0-255"
-   )]
-   #[expect(
-      clippy::missing_asserts_for_indexing,
-      reason = "length check at function start ensures indices are valid"
-   )]
-   fn animate(frames: &[i32], target_time: f64) -> Result<String, Error> {
-      if frames.len() < MIN_FRAME_VALUES {
-         return Err(Error::Parse(format!(
-            "frame has {} values, need at least {MIN_FRAME_VALUES}",
-            frames.len()
-         )));
-      }
+   /// Fingerprint of the verification key, for telling keys apart in logs
+   /// without revealing them.
+   #[must_use]
+   pub fn key_fingerprint(&self) -> String {
+      fingerprint(&self.key_bytes)
+   }
 
-      let from_color = frames[..3]
-         .iter()
-         .map(|&val| f64::from(val))
-         .chain(iter::once(1.0))
-         .collect::<Vec<f64>>();
+   /// Whether the key material should be refreshed under `policy`.
+   #[must_use]
+   pub fn is_stale(&self, policy: &StalePolicy) -> bool {
+      self.age() >= policy.max_age
+         || policy
+            .ondemand_hash
+            .as_deref()
+            .is_some_and(|hash| self.ondemand_hash.as_deref() != Some(hash))
+   }
 
-      let to_color = frames[3..6]
-         .iter()
-         .map(|&val| f64::from(val))
-         .chain(iter::once(1.0))
-         .collect::<Vec<f64>>();
+   /// Seconds X's clock is ahead of the local one; negative if it is
+   /// behind.
+   #[must_use]
+   pub fn clock_offset(&self) -> i64 {
+      self.clock_offset.seconds.load(Ordering::Relaxed)
+   }
 
-      let from_rotation = [0.0];
-      let to_rotation = [Self::solve(f64::from(frames[6]), 60.0, 360.0, true)];
+   /// Sets the [`clock_offset`](Self::clock_offset), for this client and
+   /// its clones.
+   pub fn set_clock_offset(&self, seconds: i64) {
+      self.clock_offset.seconds.store(seconds, Ordering::Relaxed);
+      self.clock_offset.synced.store(true, Ordering::Relaxed);
+   }
 
-      let curves = frames[7..]
-         .iter()
-         .enumerate()
-         .map(|(idx, &val)| Self::solve(f64::from(val), odd_coefficient(idx), 1.0, false))
-         .collect::<Vec<f64>>();
+   /// Whether the [`clock_offset`](Self::clock_offset) was set from a `Date`
+   /// header or by hand, rather than left at `0` because no page had one.
+   #[must_use]
+   pub fn is_clock_synced(&self) -> bool {
+      self.clock_offset.synced.load(Ordering::Relaxed)
+   }
+
+   /// Sets the [`clock_offset`](Self::clock_offset) from the `Date` header
+   /// of a response X just sent, e.g. to any API request, and returns it.
+   ///
+   /// ```ignore
+   /// if let Some(date) = response.header("date") {
+   ///    client.sync_clock(date)?;
+   /// }
+   /// ```
+   pub fn sync_clock(&self, date_header: &str) -> Result<i64, Error> {
+      let server_time = parse_http_date(date_header)
+         .ok_or_else(|| Error::Parse(format!("invalid Date header `{date_header}`")))?;
+      Ok(self.sync_clock_to(server_time, SystemTime::now()))
+   }
 
-      let cubic = Cubic::new(curves);
-      let interpolation_factor = cubic.value(target_time);
+   /// Sets the offset from the server's time at the local time `local_time`.
+   fn sync_clock_to(&self, server_time: SystemTime, local_time: SystemTime) -> i64 {
+      let offset = match server_time.duration_since(local_time) {
+         Ok(ahead) => i64::try_from(ahead.as_secs()).unwrap_or(i64::MAX),
+         Err(behind) => i64::try_from(behind.duration().as_secs()).map_or(i64::MIN, |secs| -secs),
+      };
+      self.set_clock_offset(offset);
+      offset
+   }
 
-      let color = interpolate(&from_color, &to_color, interpolation_factor)?
+   /// The current time by X's clock: the local time corrected by the
+   /// [`clock_offset`](Self::clock_offset).
+   #[must_use]
+   pub fn server_time(&self) -> SystemTime {
+      let now = SystemTime::now();
+      let offset = self.clock_offset();
+      let shift = Duration::from_secs(offset.unsigned_abs());
+      if offset >= 0 {
+         now.checked_add(shift)
+      } else {
+         now.checked_sub(shift)
+      }
+      .unwrap_or(now)
+   }
+
+   /// Extracts the ondemand.s.*.js URL from homepage HTML.
+   ///
+   /// Returns the most likely of the
+   /// [`ondemand_candidates`](crate::discovery::ondemand_candidates).
+   pub fn extract_ondemand_url(home_page_html: &str) -> Result<String, Error> {
+      discovery::ondemand_candidates(home_page_html)
          .into_iter()
-         .map(|val| val.clamp(0.0, 255.0))
-         .collect::<Vec<_>>();
+         .next()
+         .map(|candidate| candidate.url)
+         .ok_or_else(|| discovery::missing(home_page_html))
+   }
 
-      let rotation = interpolate(&from_rotation, &to_rotation, interpolation_factor)?;
-      let matrix = rotation_matrix(rotation[0]);
+   /// Extracts the ondemand.s chunk hash from homepage HTML.
+   pub fn extract_ondemand_hash(home_page_html: &str) -> Result<String, Error> {
+      discovery::ondemand_hash(home_page_html)
+   }
 
-      let mut parts = Vec::with_capacity(9);
+   /// Generates a transaction ID for an API request.
+   ///
+   /// A [`server_time`](Self::server_time) outside the range of
+   /// [`XTimestamp`] is clamped to it, producing an ID X will reject; use
+   /// [`try_generate_transaction_id`](Self::try_generate_transaction_id) to
+   /// catch that.
+   #[must_use]
+   pub fn generate_transaction_id(&self, method: &str, path: &str) -> String {
+      let time = XTimestamp::saturating_from(self.server_time());
+      self.generate_transaction_id_at(method, path, time.as_secs())
+   }
 
-      for val in &color[..color.len() - 1] {
-         parts.push(format!("{:x}", val.round() as i32));
-      }
+   /// Like [`generate_transaction_id`](Self::generate_transaction_id), but
+   /// fails with [`Error::BeforeEpoch`] or [`Error::TimestampOverflow`] if
+   /// the clock is outside the range of [`XTimestamp`].
+   pub fn try_generate_transaction_id(&self, method: &str, path: &str) -> Result<String, Error> {
+      let time = XTimestamp::try_from(self.server_time())?;
+      Ok(self.generate_transaction_id_at(method, path, time.as_secs()))
+   }
 
-      for val in matrix {
-         let rounded = (val * 100.0).round() / 100.0;
-         let hex = float_to_hex(rounded.abs());
+   /// Generates a transaction ID for a fixed time, in seconds since X's
+   /// epoch (2023-05-01 07:00:00 UTC); see [`XTimestamp::as_secs`].
+   ///
+   /// With the default [`RandomSource`] the output is deterministic, which
+   /// makes it useful for tests; a [seeded](RandomSource::seeded) one
+   /// repeats the same sequence of IDs.
+   #[must_use]
+   pub fn generate_transaction_id_at(&self, method: &str, path: &str, time: u32) -> String {
+      self.random_source.next_byte().map_or_else(
+         || {
+            self
+               .algorithm
+               .generate(&self.key_bytes, &self.animation_key, method, path, time)
+         },
+         |random_byte| {
+            self.algorithm.generate_with_random_byte(
+               &self.key_bytes,
+               &self.animation_key,
+               method,
+               path,
+               time,
+               random_byte,
+            )
+         },
+      )
+   }
 
-         if hex.starts_with('.') {
-            parts.push(format!("0{}", hex.to_lowercase()));
-         } else if hex.is_empty() {
-            parts.push("0".to_owned());
-         } else {
-            parts.push(hex.to_lowercase());
-         }
+   /// Picks the frame selected by the key byte at `FRAME_SELECTOR_INDEX`.
+   fn frame_data<'a>(
+      key_bytes: &[u8],
+      frames: &'a [Vec<Vec<i32>>],
+   ) -> Result<(usize, &'a [Vec<i32>]), Error> {
+      if frames.is_empty() {
+         return Err(Error::MissingKey("animation frames".into()));
       }
 
-      parts.push("0".to_owned());
-      parts.push("0".to_owned());
+      let selector_byte = key_bytes
+         .get(FRAME_SELECTOR_INDEX)
+         .ok_or_else(|| Error::Parse("key too short for frame selection".into()))?;
+
+      let frame_index = usize::from(selector_byte % FRAME_COUNT);
+      let frame = frames
+         .get(frame_index)
+         .ok_or_else(|| Error::Parse("frame index out of bounds".into()))?;
 
-      Ok(parts.join("").replace(['.', '-'], ""))
+      Ok((frame_index, frame))
    }
 
-   fn compute_animation_key(
+   /// Returns the frame, row and time of the loading animation that the
+   /// animation key is derived from.
+   ///
+   /// Useful for checking a suspicious animation key, e.g. by rendering it
+   /// with [`render_svg`](crate::animation::render_svg).
+   pub fn select_frame(home_page_html: &str, ondemand_js: &str) -> Result<FrameSelection, Error> {
+      Self::select_frame_with_registry(home_page_html, ondemand_js, &Registry::default())
+   }
+
+   /// Like [`select_frame`](Self::select_frame), with the indices of the
+   /// algorithm version `registry` [detects](Registry::detect) in
+   /// ondemand.js.
+   pub fn select_frame_with_registry(
+      home_page_html: &str,
+      ondemand_js: &str,
+      registry: &Registry,
+   ) -> Result<FrameSelection, Error> {
+      let indices = Self::detect_algorithm(ondemand_js, registry)?.indices(ondemand_js)?;
+      let key_bytes = parse::decode_verification_key(&parse::verification_key(home_page_html)?)?;
+      Self::frame_selection(
+         &key_bytes,
+         &parse::animation_frames(home_page_html),
+         &indices,
+      )
+   }
+
+   pub(crate) fn frame_selection(
       key_bytes: &[u8],
-      html: &str,
-      row_index: usize,
-      key_bytes_indices: &[usize],
-   ) -> Result<String, Error> {
+      frames: &[Vec<Vec<i32>>],
+      indices: &OndemandIndices,
+   ) -> Result<FrameSelection, Error> {
       let row_selector = key_bytes
-         .get(row_index)
+         .get(indices.row_index())
          .ok_or_else(|| Error::Parse("key too short for row selection".into()))?;
       let row_index_value = usize::from(row_selector % ROW_INDEX_MODULUS);
 
-      let frame_time = key_bytes_indices
+      let frame_time = indices
+         .key_byte_indices()
          .iter()
          .filter_map(|&index| key_bytes.get(index))
          .map(|&byte| f64::from(byte % ROW_INDEX_MODULUS))
@@ -442,33 +693,52 @@ impl ClientTransaction {
 
       let frame_time = js_round(frame_time / 10.0) * 10.0;
 
-      let arr = Self::frame_data(key_bytes, html)?;
+      let (frame_index, arr) = Self::frame_data(key_bytes, frames)?;
 
-      let frame = arr
+      let row = arr
          .get(row_index_value)
          .ok_or_else(|| Error::Parse("row index out of bounds".into()))?;
 
-      let target_time = frame_time / TOTAL_ANIMATION_TIME;
-      Self::animate(frame, target_time)
+      Ok(FrameSelection {
+         frame_index,
+         row_index: row_index_value,
+         row: row.clone(),
+         target_time: frame_time / TOTAL_ANIMATION_TIME,
+      })
+   }
+}
+
+impl fmt::Debug for ClientTransaction {
+   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      f.debug_struct("ClientTransaction")
+         .field("version", &self.algorithm.version())
+         .field("key_fingerprint", &self.key_fingerprint())
+         .field("indices", &self.indices)
+         .field("ondemand_hash", &self.ondemand_hash)
+         .field("home_page_hash", &self.home_page_hash)
+         .field("created_at", &self.created_at)
+         .field("age", &self.age())
+         .field("clock_offset", &self.clock_offset())
+         .field("clock_synced", &self.is_clock_synced())
+         .field("random_source", &self.random_source)
+         .finish_non_exhaustive()
    }
 }
 
+/// Hex of the first 8 bytes of the SHA-256 of `data`.
+fn fingerprint(data: &[u8]) -> String {
+   HEXLOWER.encode(&Hash::hash(data)[..8])
+}
+
 #[cfg(test)]
 mod tests {
-   use super::*;
-
-   #[test]
-   fn verification_key_extraction() {
-      let html = r#"<html><head><meta name="twitter-site-verification" content="abc123xyz"/></head></html>"#;
-      let key = ClientTransaction::verification_key(html).unwrap();
-      assert_eq!(key, "abc123xyz");
-   }
+   use std::time::UNIX_EPOCH;
 
-   #[test]
-   fn verification_key_missing() {
-      let html = "<html><head></head></html>";
-      ClientTransaction::verification_key(html).unwrap_err();
-   }
+   use super::*;
+   use crate::{
+      testing::SyntheticPages,
+      utils::base64_encode,
+   };
 
    #[test]
    fn ondemand_url_extraction() {
@@ -497,31 +767,283 @@ mod tests {
    }
 
    #[test]
-   fn parse_path_coordinates() {
-      let path = "M0 0 0 0C10 20 30 40 50 60C70 80 90 100 110 120";
-      let result = ClientTransaction::parse_path_to_coordinates(path);
-      assert_eq!(result.len(), 2);
-      assert!(!result[0].is_empty());
-      assert!(!result[1].is_empty());
+   fn clock_offset() {
+      let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>());
+      let client = ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap();
+      let clone = client.clone();
+      assert_eq!(client.clock_offset(), 0);
+      assert!(!client.is_clock_synced());
+
+      let offset = client.sync_clock("Mon, 01 May 2023 06:59:00 GMT").unwrap();
+      assert!(offset < 0);
+      assert_eq!(clone.clock_offset(), offset);
+      assert!(clone.is_clock_synced());
+      match clone.try_generate_transaction_id("GET", "/") {
+         Err(Error::BeforeEpoch(_)) => {},
+         other => panic!("unexpected {other:?}"),
+      }
+
+      client.set_clock_offset(3_600);
+      let ahead = client
+         .server_time()
+         .duration_since(SystemTime::now())
+         .unwrap();
+      assert!(ahead > Duration::from_secs(3_590));
+
+      client.sync_clock("yesterday").unwrap_err();
+      client
+         .sync_clock("Sun, 06 Nov 99999999999999 08:49:37 GMT")
+         .unwrap_err();
+      assert_eq!(client.clock_offset(), 3_600);
+   }
+
+   #[test]
+   fn frame_selection() {
+      let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>()).with_indices(2, vec![12, 14]);
+      let selection =
+         ClientTransaction::select_frame(&pages.home_page_html(), &pages.ondemand_js()).unwrap();
+
+      // Key byte 5 picks the frame, key byte 2 the row.
+      assert_eq!(selection.frame_index, 1);
+      assert_eq!(selection.row_index, 2);
+      assert_eq!(selection.row, pages.frames()[1][2]);
+      assert!((selection.target_time - 170.0 / 4096.0).abs() < f64::EPSILON);
+   }
+
+   #[test]
+   fn from_parts_matches_new() {
+      let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>()).with_indices(4, vec![1, 9]);
+      let client = ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap();
+
+      let indices = OndemandIndices::new(4, vec![1, 9]);
+      let from_bytes =
+         ClientTransaction::from_parts(pages.key_bytes(), indices.clone(), pages.frames()).unwrap();
+      let key = parse::verification_key(&pages.home_page_html()).unwrap();
+      let from_base64 =
+         ClientTransaction::from_base64_parts(&key, indices.clone(), pages.frames()).unwrap();
+
+      for built in [from_bytes, from_base64] {
+         assert_eq!(built.animation_key(), client.animation_key());
+         assert_eq!(
+            built.generate_transaction_id_at("GET", "/i/api/1.1/foo.json", 7),
+            client.generate_transaction_id_at("GET", "/i/api/1.1/foo.json", 7)
+         );
+      }
+
+      match ClientTransaction::from_parts(pages.key_bytes(), indices, &[]) {
+         Err(Error::MissingKey(_)) => {},
+         Err(err) => panic!("unexpected error: {err}"),
+         Ok(_) => panic!("built without frames"),
+      }
    }
 
    #[test]
-   fn animate_insufficient_frames() {
-      let frames = vec![1, 2, 3];
-      ClientTransaction::animate(&frames, 0.5).unwrap_err();
+   fn reuses_indices_for_same_hash() {
+      let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>()).with_indices(4, vec![1, 9]);
+      let client = ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap();
+      assert_eq!(client.ondemand_hash(), Some(pages.ondemand_hash()));
+
+      let rotated = SyntheticPages::new((48..96).collect::<Vec<u8>>());
+      let indices = client
+         .reusable_indices(&rotated.home_page_html())
+         .unwrap()
+         .clone();
+      let refreshed = ClientTransaction::with_indices(&rotated.home_page_html(), indices).unwrap();
+      assert_eq!(refreshed.key_bytes(), rotated.key_bytes());
+      assert_eq!(refreshed.indices(), client.indices());
+      assert_eq!(refreshed.ondemand_hash(), None);
+      assert!(
+         refreshed
+            .reusable_indices(&rotated.home_page_html())
+            .is_none()
+      );
+
+      let redeployed = rotated.with_ondemand_hash("ffff0000");
+      assert!(
+         client
+            .reusable_indices(&redeployed.home_page_html())
+            .is_none()
+      );
    }
 
    #[test]
-   fn indices_parsing() {
-      let js = "foo(e[5], 16)bar(e[10], 16)padding";
-      let (row_index, indices) = ClientTransaction::parse_indices(js).unwrap();
-      assert_eq!(row_index, 5);
-      assert_eq!(indices, vec![10]);
+   fn metadata() {
+      let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>());
+      let client = ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap();
+
+      assert_eq!(client.key_fingerprint().len(), 16);
+      assert_eq!(client.home_page_hash().map(str::len), Some(16));
+      assert!(client.age() < Duration::from_mins(1));
+      assert!(!client.is_stale(&StalePolicy::default()));
+      assert!(client.is_stale(&StalePolicy {
+         max_age: Duration::ZERO,
+         ..StalePolicy::default()
+      }));
+      assert!(client.is_stale(&StalePolicy {
+         ondemand_hash: Some("ffff0000".into()),
+         ..StalePolicy::default()
+      }));
+      assert!(!client.is_stale(&StalePolicy {
+         ondemand_hash: Some(pages.ondemand_hash().to_owned()),
+         ..StalePolicy::default()
+      }));
+
+      let old = client.with_provenance(UNIX_EPOCH, None);
+      assert!(old.is_stale(&StalePolicy::default()));
+      assert_eq!(old.home_page_hash(), None);
    }
 
    #[test]
-   fn indices_missing() {
-      let js = "no indices here";
-      ClientTransaction::parse_indices(js).unwrap_err();
+   fn debug_redacts_key_material() {
+      let pages = SyntheticPages::new([0xAB; 48]);
+      let client = ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap();
+      let debug = format!("{client:?}");
+
+      assert!(debug.contains(&client.key_fingerprint()));
+      assert!(!debug.contains(client.animation_key()));
+      assert!(!debug.contains(&base64_encode(client.key_bytes())));
+   }
+
+   mod properties {
+      use proptest::{
+         collection::vec,
+         prelude::*,
+         sample::select,
+      };
+
+      use super::*;
+      use crate::{
+         decode::DecodedTransactionId,
+         graphql::Catalog,
+         manifest::ChunkManifest,
+         testing::{
+            ROWS_PER_FRAME,
+            VALUES_PER_ROW,
+         },
+      };
+
+      /// Fragments of the markup and code the parsers look for, so random
+      /// inputs reach the slicing logic instead of failing the first `find`.
+      const FRAGMENTS: &[&str] = &[
+         "<",
+         ">",
+         "\"",
+         "'",
+         ":",
+         "/>",
+         "></path>",
+         "<meta ",
+         "name=\"twitter-site-verification\"",
+         "content=\"",
+         "\"ondemand.s\"",
+         "'ondemand.s'",
+         "ondemand.s.",
+         "a.js",
+         "href=\"",
+         "src='",
+         "//",
+         "8712:",
+         "{",
+         "}",
+         ",",
+         "<script src=\"",
+         ".js",
+         "queryId:\"",
+         "operationName:\"",
+         "featureSwitches:[",
+         "]",
+         "\\",
+         "id=\"loading-x-anim",
+         "</svg>",
+         "<path",
+         " d=\"",
+         "M 10,30 ",
+         "C",
+         "-",
+         "(e[",
+         "], 16)",
+         "],16)",
+         "0",
+         "42",
+         "é",
+         "日本",
+         "🦀",
+      ];
+
+      fn markup() -> impl Strategy<Value = String> {
+         let fragment = prop_oneof![select(FRAGMENTS).prop_map(str::to_owned), "\\PC{0,8}",];
+         vec(fragment, 0..64).prop_map(|parts| parts.concat())
+      }
+
+      fn frames() -> impl Strategy<Value = Vec<Vec<Vec<i32>>>> {
+         let row = vec(0..=255_i32, VALUES_PER_ROW);
+         vec(vec(row, ROWS_PER_FRAME), 4)
+      }
+
+      fn pages() -> impl Strategy<Value = SyntheticPages> {
+         (vec(any::<u8>(), 6..64), frames(), "[a-f0-9]{1,12}").prop_flat_map(
+            |(key, frames, hash)| {
+               let len = key.len();
+               (0..len, vec(0..len, 0..6)).prop_map(move |(row_index, indices)| {
+                  SyntheticPages::new(key.clone())
+                     .with_frames(frames.clone())
+                     .with_indices(row_index, indices)
+                     .with_ondemand_hash(hash.clone())
+               })
+            },
+         )
+      }
+
+      proptest! {
+         #[test]
+         fn parsers_never_panic(input in prop_oneof![markup(), "\\PC*"]) {
+            let _ = parse::verification_key(&input);
+            let _ = discovery::ondemand_candidates(&input);
+            let _ = ChunkManifest::parse(&input);
+            let _ = Catalog::parse(&input);
+            let _ = OndemandIndices::parse(&input);
+            let _ = parse::animation_frames(&input);
+            let _ = parse::path_coordinates(&input);
+            let _ = ClientTransaction::new(&input, &input);
+         }
+
+         #[test]
+         fn verification_key_round_trips(pages in pages()) {
+            let key = parse::verification_key(&pages.home_page_html()).unwrap();
+            prop_assert_eq!(parse::decode_verification_key(&key).unwrap(), pages.key_bytes());
+         }
+
+         #[test]
+         fn ondemand_url_round_trips(pages in pages()) {
+            let url = ClientTransaction::extract_ondemand_url(&pages.home_page_html()).unwrap();
+            prop_assert_eq!(
+               url,
+               ondemand_url(ONDEMAND_URL_TEMPLATE, pages.ondemand_hash())
+            );
+         }
+
+         #[test]
+         fn indices_round_trip(pages in pages()) {
+            let indices = OndemandIndices::parse(&pages.ondemand_js()).unwrap();
+            prop_assert_eq!(indices.row_index(), pages.row_index());
+            prop_assert_eq!(indices.key_byte_indices(), pages.key_byte_indices());
+            prop_assert_eq!(indices.to_string().parse::<OndemandIndices>().unwrap(), indices);
+         }
+
+         #[test]
+         fn frames_round_trip(pages in pages()) {
+            prop_assert_eq!(parse::animation_frames(&pages.home_page_html()), pages.frames());
+         }
+
+         #[test]
+         fn transaction_id_round_trips(pages in pages(), time in any::<u32>()) {
+            let client = ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap();
+            let id = client.generate_transaction_id_at("GET", "/i/api/1.1/foo.json", time);
+            let decoded = DecodedTransactionId::decode(&id).unwrap();
+            prop_assert_eq!(&decoded.key_bytes, pages.key_bytes());
+            prop_assert_eq!(decoded.time, time);
+            prop_assert!(decoded.is_current_version());
+         }
+      }
    }
 }
@@ -1,6 +1,22 @@
 //! Encoding and numeric utilities.
 
-use data_encoding::BASE64;
+use std::{
+   hash::{
+      BuildHasher as _,
+      Hasher as _,
+      RandomState,
+   },
+   time::{
+      Duration,
+      SystemTime,
+      UNIX_EPOCH,
+   },
+};
+
+use data_encoding::{
+   BASE64,
+   BASE64_NOPAD,
+};
 
 /// Returns -1.0 for odd numbers, 0.0 for even. Used in bezier control point
 /// calculation.
@@ -91,6 +107,85 @@ pub fn base64_decode(input: &str) -> Result<Vec<u8>, data_encoding::DecodeError>
    BASE64.decode(input.as_bytes())
 }
 
+/// Decodes base64 with or without trailing `=` padding.
+pub fn base64_decode_unpadded(input: &str) -> Result<Vec<u8>, data_encoding::DecodeError> {
+   BASE64_NOPAD.decode(input.trim_end_matches('=').as_bytes())
+}
+
+/// Returns a random number from the process's OS-seeded hasher keys.
+///
+/// Not cryptographically secure; good enough for jitter.
+pub fn random_u64() -> u64 {
+   let mut hasher = RandomState::new().build_hasher();
+   hasher.write_u128(
+      SystemTime::now()
+         .duration_since(UNIX_EPOCH)
+         .map_or(0, |duration| duration.as_nanos()),
+   );
+   hasher.finish()
+}
+
+/// Parses an HTTP date in the IMF-fixdate format, e.g.
+/// `Sun, 06 Nov 1994 08:49:37 GMT`. Years outside `1970..=9999` are
+/// rejected, which keeps the date math from overflowing.
+pub fn parse_http_date(date: &str) -> Option<SystemTime> {
+   let mut parts = date.trim().split_ascii_whitespace();
+   let _weekday = parts.next()?.strip_suffix(',')?;
+   let day = parts.next()?.parse::<u32>().ok()?;
+   let month = match parts.next()? {
+      "Jan" => 1,
+      "Feb" => 2,
+      "Mar" => 3,
+      "Apr" => 4,
+      "May" => 5,
+      "Jun" => 6,
+      "Jul" => 7,
+      "Aug" => 8,
+      "Sep" => 9,
+      "Oct" => 10,
+      "Nov" => 11,
+      "Dec" => 12,
+      _ => return None,
+   };
+   let year = parts.next()?.parse::<i64>().ok()?;
+
+   let mut time = parts
+      .next()?
+      .split(':')
+      .map(|part| part.parse::<u64>().ok());
+   let hour = time.next()??;
+   let minute = time.next()??;
+   let second = time.next()??;
+
+   if parts.next()? != "GMT"
+      || parts.next().is_some()
+      || time.next().is_some()
+      || !(1970..=9999).contains(&year)
+      || !(1..=31).contains(&day)
+      || hour > 23
+      || minute > 59
+      || second > 60
+   {
+      return None;
+   }
+
+   let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+   let secs = days * 86_400 + hour * 3_600 + minute * 60 + second;
+   UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+}
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+   let year = if month <= 2 { year - 1 } else { year };
+   let era = year.div_euclid(400);
+   let year_of_era = year.rem_euclid(400);
+   let month = i64::from(month);
+   let day_of_year =
+      (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+   let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+   era * 146_097 + day_of_era - 719_468
+}
+
 #[cfg(test)]
 mod tests {
    use super::*;
@@ -153,6 +248,52 @@ mod tests {
       assert!(decoded.is_empty());
    }
 
+   #[test]
+   fn base64_unpadded() {
+      assert_eq!(base64_decode_unpadded("YWI").unwrap(), b"ab");
+      assert_eq!(base64_decode_unpadded("YWI=").unwrap(), b"ab");
+   }
+
+   #[test]
+   fn http_date() {
+      let date = parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+      assert_eq!(
+         date.duration_since(UNIX_EPOCH).unwrap().as_secs(),
+         784_111_777
+      );
+
+      let date = parse_http_date("Mon, 01 May 2023 00:00:00 GMT").unwrap();
+      assert_eq!(
+         date.duration_since(UNIX_EPOCH).unwrap().as_secs(),
+         1_682_899_200
+      );
+
+      let date = parse_http_date("Fri, 31 Dec 9999 23:59:59 GMT").unwrap();
+      assert_eq!(
+         date.duration_since(UNIX_EPOCH).unwrap().as_secs(),
+         253_402_300_799
+      );
+   }
+
+   #[test]
+   fn http_date_invalid() {
+      assert!(parse_http_date("").is_none());
+      assert!(parse_http_date("120").is_none());
+      assert!(parse_http_date("Sun, 06 Nov 1994 08:49:37 PST").is_none());
+      assert!(parse_http_date("Sun, 06 Foo 1994 08:49:37 GMT").is_none());
+      assert!(parse_http_date("Sun, 32 Nov 1994 08:49:37 GMT").is_none());
+      assert!(parse_http_date("Sun, 06 Nov 1994 24:00:00 GMT").is_none());
+      assert!(parse_http_date("Sun, 06 Nov 1969 08:49:37 GMT").is_none());
+      assert!(parse_http_date("Sun, 06 Nov 99999999999999 08:49:37 GMT").is_none());
+      assert!(parse_http_date(&format!("Sun, 06 Nov {} 08:49:37 GMT", i64::MAX)).is_none());
+      assert!(parse_http_date(&format!("Sun, 06 Nov {} 08:49:37 GMT", i64::MIN)).is_none());
+   }
+
+   #[test]
+   fn random_values_differ() {
+      assert_ne!(random_u64(), random_u64());
+   }
+
    #[test]
    fn base64_invalid() {
       base64_decode("not valid base64!!!").unwrap_err();
@@ -0,0 +1,289 @@
+//! `FileCache` tests against a local server: fresh hits, revalidation and
+//! falling back to a stale copy.
+
+#![cfg(feature = "fetch")]
+#![expect(
+   clippy::tests_outside_test_module,
+   reason = "integration tests are a crate of their own"
+)]
+
+mod common;
+
+use std::{
+   env,
+   fs,
+   path::PathBuf,
+   process,
+   time::{
+      Duration,
+      SystemTime,
+      UNIX_EPOCH,
+   },
+};
+
+use common::{
+   Response,
+   StubServer,
+};
+use xitter_txid::{
+   ClientTransaction,
+   FetchOptions,
+   FileCache,
+   RetryPolicy,
+   testing::SyntheticPages,
+};
+
+const ETAG: &str = "\"home-v1\"";
+
+fn pages(hash: &str) -> SyntheticPages {
+   SyntheticPages::new((0..48).collect::<Vec<u8>>()).with_ondemand_hash(hash)
+}
+
+/// Serves an ETag-validated homepage at `/home`, answering `304` when the
+/// `ETag` matches, and ondemand chunks at `/ondemand/<hash>.js`.
+fn server() -> StubServer {
+   StubServer::start(|request, _| {
+      match request.path.as_str() {
+         "/home" if request.header("If-None-Match") == Some(ETAG) => Response::status(304),
+         "/home" => Response::ok(pages("abc123").home_page_html()).header("ETag", ETAG),
+         "/ondemand/abc123.js" => Response::ok(pages("abc123").ondemand_js()),
+         _ => Response::status(404),
+      }
+   })
+}
+
+/// An empty cache directory unique to `name`.
+fn cache_dir(name: &str) -> PathBuf {
+   let dir = env::temp_dir().join(format!("xitter-txid-{name}-{}", process::id()));
+   let _ = fs::remove_dir_all(&dir);
+   dir
+}
+
+fn options(base_url: &str, cache: FileCache) -> FetchOptions {
+   FetchOptions {
+      home_url: format!("{base_url}/home"),
+      ondemand_url_template: format!("{base_url}/ondemand/{{hash}}.js"),
+      retry: RetryPolicy::none(),
+      cache: Some(cache),
+      ..FetchOptions::default()
+   }
+}
+
+#[test]
+fn fresh_entries_skip_the_network() {
+   let server = server();
+   let cache = FileCache::new(cache_dir("fresh"));
+   let options = options(&server.url(""), cache.clone());
+
+   let first = ClientTransaction::fetch_with(&options).unwrap();
+   let second = ClientTransaction::fetch_with(&options).unwrap();
+
+   assert_eq!(first.key_bytes(), second.key_bytes());
+   assert_eq!(server.hits("/home"), 1);
+   assert_eq!(server.hits("/ondemand/abc123.js"), 1);
+   cache.clear().unwrap();
+}
+
+#[test]
+fn cached_homepage_keeps_its_age() {
+   let server = server();
+   let cache = FileCache::new(cache_dir("age"));
+   let options = options(&server.url(""), cache.clone());
+   ClientTransaction::fetch_with(&options).unwrap();
+
+   // Date the cached homepage half an hour back, still within the TTL.
+   let fetched_at = SystemTime::now() - Duration::from_mins(30);
+   let secs = fetched_at.duration_since(UNIX_EPOCH).unwrap().as_secs();
+   for entry in fs::read_dir(&cache.dir).unwrap() {
+      let path = entry.unwrap().path();
+      let name = path.file_name().unwrap().to_str().unwrap();
+      if name.starts_with("home-")
+         && path
+            .extension()
+            .is_some_and(|extension| extension == "meta")
+      {
+         let meta = fs::read_to_string(&path).unwrap();
+         let (_, rest) = meta.split_once('\n').unwrap();
+         fs::write(&path, format!("fetched_at = {secs}\n{rest}")).unwrap();
+      }
+   }
+
+   let client = ClientTransaction::fetch_with(&options).unwrap();
+   assert_eq!(server.hits("/home"), 1);
+   assert!(client.age() >= Duration::from_mins(30));
+   cache.clear().unwrap();
+}
+
+#[test]
+fn stale_homepage_is_revalidated() {
+   let server = server();
+   let cache = FileCache {
+      ttl: Duration::ZERO,
+      ..FileCache::new(cache_dir("revalidate"))
+   };
+   let options = options(&server.url(""), cache.clone());
+
+   ClientTransaction::fetch_with(&options).unwrap();
+   ClientTransaction::fetch_with(&options).unwrap();
+
+   let requests = server.requests();
+   let homes = requests
+      .iter()
+      .filter(|request| request.path == "/home")
+      .collect::<Vec<_>>();
+   assert_eq!(homes.len(), 2);
+   assert_eq!(homes[0].header("If-None-Match"), None);
+   assert_eq!(homes[1].header("If-None-Match"), Some(ETAG));
+   assert_eq!(server.hits("/ondemand/abc123.js"), 1);
+   cache.clear().unwrap();
+}
+
+#[test]
+fn falls_back_to_stale_copy() {
+   let server = StubServer::start(|request, count| {
+      match request.path.as_str() {
+         "/home" if count > 0 => Response::status(503),
+         "/home" => Response::ok(pages("abc123").home_page_html()),
+         _ => Response::ok(pages("abc123").ondemand_js()),
+      }
+   });
+   let cache = FileCache {
+      ttl: Duration::ZERO,
+      ..FileCache::new(cache_dir("fallback"))
+   };
+
+   let expected = ClientTransaction::fetch_with(&options(&server.url(""), cache.clone())).unwrap();
+   let fetched = ClientTransaction::fetch_with(&options(&server.url(""), cache.clone())).unwrap();
+   assert_eq!(fetched.key_bytes(), expected.key_bytes());
+   assert_eq!(server.hits("/home"), 2);
+
+   let strict = FileCache {
+      stale_on_error: false,
+      ..cache.clone()
+   };
+   ClientTransaction::fetch_with(&options(&server.url(""), strict))
+      .map(|_| ())
+      .unwrap_err();
+   cache.clear().unwrap();
+}
+
+#[test]
+fn evicts_oldest_ondemand_chunks() {
+   let cache = FileCache {
+      max_entries: 1,
+      ..FileCache::new(cache_dir("evict"))
+   };
+
+   for hash in ["aaa111", "bbb222"] {
+      let server = StubServer::start(move |request, _| {
+         if request.path == "/home" {
+            Response::ok(pages(hash).home_page_html())
+         } else {
+            Response::ok(pages(hash).ondemand_js())
+         }
+      });
+      ClientTransaction::fetch_with(&options(&server.url(""), cache.clone())).unwrap();
+   }
+
+   let mut files = fs::read_dir(&cache.dir)
+      .unwrap()
+      .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+      .filter(|name| name.starts_with("ondemand-"))
+      .collect::<Vec<_>>();
+   files.sort();
+   assert_eq!(files, ["ondemand-bbb222.body", "ondemand-bbb222.meta"]);
+   cache.clear().unwrap();
+}
+
+#[test]
+fn zero_max_entries_keeps_everything() {
+   let cache = FileCache {
+      max_entries: 0,
+      ..FileCache::new(cache_dir("unbounded"))
+   };
+   let server = server();
+   ClientTransaction::fetch_with(&options(&server.url(""), cache.clone())).unwrap();
+
+   let mut files = fs::read_dir(&cache.dir)
+      .unwrap()
+      .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+      .filter(|name| name.starts_with("ondemand-"))
+      .collect::<Vec<_>>();
+   files.sort();
+   assert_eq!(files, ["ondemand-abc123.body", "ondemand-abc123.meta"]);
+   cache.clear().unwrap();
+}
+
+#[test]
+fn body_without_matching_meta_is_refetched() {
+   let cache = FileCache::new(cache_dir("torn"));
+   let server = server();
+   ClientTransaction::fetch_with(&options(&server.url(""), cache.clone())).unwrap();
+
+   // Another writer's body, renamed in before its meta.
+   let body = cache.dir.join("ondemand-abc123.body");
+   fs::write(&body, "torn").unwrap();
+   ClientTransaction::fetch_with(&options(&server.url(""), cache.clone())).unwrap();
+
+   assert_eq!(server.hits("/ondemand/abc123.js"), 2);
+   assert_ne!(fs::read_to_string(&body).unwrap(), "torn");
+   cache.clear().unwrap();
+}
+
+#[test]
+fn cached_pages_keep_their_server_date() {
+   let server = StubServer::start(|request, _| {
+      if request.path == "/home" {
+         Response::ok(pages("abc123").home_page_html())
+      } else {
+         Response::ok(pages("abc123").ondemand_js()).header("Date", "Fri, 01 Jan 2100 00:00:00 GMT")
+      }
+   });
+   let cache = FileCache::new(cache_dir("clock"));
+   let options = options(&server.url(""), cache.clone());
+
+   let fetched = ClientTransaction::fetch_with(&options).unwrap();
+   assert!(fetched.is_clock_synced());
+   assert!(fetched.clock_offset() > 0);
+
+   // Both pages come from the cache, and fetched_at is kept to the second.
+   let cached = ClientTransaction::fetch_with(&options).unwrap();
+   assert_eq!(server.hits("/ondemand/abc123.js"), 1);
+   assert!(cached.is_clock_synced());
+   assert!((cached.clock_offset() - fetched.clock_offset()).abs() <= 1);
+
+   let refreshed = fetched.refresh_with(&options).unwrap();
+   assert!((refreshed.clock_offset() - fetched.clock_offset()).abs() <= 1);
+   cache.clear().unwrap();
+}
+
+#[test]
+fn pages_without_a_date_leave_the_clock_alone() {
+   let server = server();
+   let cache = FileCache::new(cache_dir("no-date"));
+   let options = options(&server.url(""), cache.clone());
+
+   let fetched = ClientTransaction::fetch_with(&options).unwrap();
+   let cached = ClientTransaction::fetch_with(&options).unwrap();
+   for client in [fetched, cached] {
+      assert!(!client.is_clock_synced());
+      assert_eq!(client.clock_offset(), 0);
+   }
+   cache.clear().unwrap();
+}
+
+#[test]
+fn out_of_range_fetched_at_is_a_miss() {
+   let cache = FileCache::new(cache_dir("overflow"));
+   let server = server();
+   ClientTransaction::fetch_with(&options(&server.url(""), cache.clone())).unwrap();
+
+   let meta = cache.dir.join("ondemand-abc123.meta");
+   let contents = fs::read_to_string(&meta).unwrap();
+   let (_, rest) = contents.split_once('\n').unwrap();
+   fs::write(&meta, format!("fetched_at = {}\n{rest}", u64::MAX)).unwrap();
+   ClientTransaction::fetch_with(&options(&server.url(""), cache.clone())).unwrap();
+
+   assert_eq!(server.hits("/ondemand/abc123.js"), 2);
+   cache.clear().unwrap();
+}
@@ -0,0 +1,185 @@
+//! A scripted HTTP server on localhost, so fetch tests need no network.
+
+#![allow(dead_code, reason = "not every test binary uses every helper")]
+
+use std::{
+   collections::HashMap,
+   fmt::Write as _,
+   io::{
+      BufRead as _,
+      BufReader,
+      Read as _,
+      Write as _,
+   },
+   net::{
+      TcpListener,
+      TcpStream,
+   },
+   sync::{
+      Arc,
+      Mutex,
+   },
+   thread,
+};
+
+/// A request the server received.
+#[derive(Debug, Clone)]
+pub struct Request {
+   pub method:  String,
+   pub path:    String,
+   pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+   /// Value of the first header named `name`, ignoring case.
+   pub fn header(&self, name: &str) -> Option<&str> {
+      self
+         .headers
+         .iter()
+         .find(|&&(ref key, _)| key.eq_ignore_ascii_case(name))
+         .map(|&(_, ref value)| value.as_str())
+   }
+}
+
+/// A response for the server to send.
+#[derive(Debug, Clone)]
+pub struct Response {
+   pub status:  u16,
+   pub headers: Vec<(String, String)>,
+   pub body:    String,
+}
+
+impl Response {
+   pub fn ok(body: impl Into<String>) -> Self {
+      Self::status(200).body(body)
+   }
+
+   pub const fn status(status: u16) -> Self {
+      Self {
+         status,
+         headers: Vec::new(),
+         body: String::new(),
+      }
+   }
+
+   #[must_use]
+   pub fn body(mut self, body: impl Into<String>) -> Self {
+      self.body = body.into();
+      self
+   }
+
+   #[must_use]
+   pub fn header(mut self, name: &str, value: &str) -> Self {
+      self.headers.push((name.to_owned(), value.to_owned()));
+      self
+   }
+}
+
+type Handler = dyn Fn(&Request, usize) -> Response + Send + Sync;
+
+/// Serves responses from a handler that gets each request and how many
+/// earlier requests were made to the same path.
+pub struct StubServer {
+   base_url: String,
+   requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl StubServer {
+   pub fn start(handler: impl Fn(&Request, usize) -> Response + Send + Sync + 'static) -> Self {
+      let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+      let base_url = format!("http://{}", listener.local_addr().unwrap());
+      let requests = Arc::new(Mutex::new(Vec::new()));
+
+      let log = Arc::clone(&requests);
+      let handler: Arc<Handler> = Arc::new(handler);
+      thread::spawn(move || {
+         let mut counts = HashMap::<String, usize>::new();
+         for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let Some(request) = read_request(&stream) else {
+               continue;
+            };
+
+            let count = counts.entry(request.path.clone()).or_default();
+            let response = handler(&request, *count);
+            *count += 1;
+
+            log.lock().unwrap().push(request);
+            write_response(stream, &response);
+         }
+      });
+
+      Self { base_url, requests }
+   }
+
+   /// Absolute URL for `path` on this server.
+   pub fn url(&self, path: &str) -> String {
+      format!("{}{path}", self.base_url)
+   }
+
+   pub fn requests(&self) -> Vec<Request> {
+      self.requests.lock().unwrap().clone()
+   }
+
+   /// Number of requests made to `path`.
+   pub fn hits(&self, path: &str) -> usize {
+      self
+         .requests()
+         .iter()
+         .filter(|request| request.path == path)
+         .count()
+   }
+}
+
+fn read_request(stream: &TcpStream) -> Option<Request> {
+   let mut reader = BufReader::new(stream);
+   let mut line = String::new();
+   reader.read_line(&mut line).ok()?;
+
+   let mut parts = line.split_whitespace();
+   let method = parts.next()?.to_owned();
+   let path = parts.next()?.to_owned();
+
+   let mut headers = Vec::new();
+   let mut content_length = 0;
+   loop {
+      line.clear();
+      reader.read_line(&mut line).ok()?;
+      let line = line.trim_end();
+      if line.is_empty() {
+         break;
+      }
+      if let Some((name, value)) = line.split_once(':') {
+         let value = value.trim();
+         if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse().unwrap_or(0);
+         }
+         headers.push((name.to_owned(), value.to_owned()));
+      }
+   }
+
+   let mut body = vec![0; content_length];
+   reader.read_exact(&mut body).ok()?;
+
+   Some(Request {
+      method,
+      path,
+      headers,
+   })
+}
+
+fn write_response(mut stream: TcpStream, response: &Response) {
+   let mut head = format!(
+      "HTTP/1.1 {} Stub\r\nContent-Length: {}\r\nConnection: close\r\n",
+      response.status,
+      response.body.len()
+   );
+   for &(ref name, ref value) in &response.headers {
+      let _ = write!(head, "{name}: {value}\r\n");
+   }
+   head.push_str("\r\n");
+
+   let _ = stream.write_all(head.as_bytes());
+   let _ = stream.write_all(response.body.as_bytes());
+   let _ = stream.flush();
+}
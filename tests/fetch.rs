@@ -0,0 +1,263 @@
+//! End-to-end `fetch_with` tests against a local mirror, with no outbound
+//! network.
+
+#![cfg(feature = "fetch")]
+#![expect(
+   clippy::tests_outside_test_module,
+   reason = "integration tests are a crate of their own"
+)]
+
+mod common;
+
+use common::{
+   Response,
+   StubServer,
+};
+use xitter_txid::{
+   ClientPool,
+   ClientTransaction,
+   DecodedTransactionId,
+   Error,
+   FetchOptions,
+   RetryPolicy,
+   Session,
+   WebClientContext,
+   testing::{
+      BEARER_TOKEN,
+      SyntheticPages,
+      main_js,
+   },
+};
+
+const HASH: &str = "feed42";
+
+fn pages() -> SyntheticPages {
+   SyntheticPages::new((100..148).collect::<Vec<u8>>())
+      .with_indices(7, vec![3, 30, 41])
+      .with_ondemand_hash(HASH)
+}
+
+/// Serves the homepage at `/home` and the ondemand chunk at
+/// `/mirror/<hash>.js`, like a caching mirror with its own layout.
+fn mirror() -> StubServer {
+   StubServer::start(|request, _| {
+      match request.path.as_str() {
+         "/home" => Response::ok(pages().home_page_html()),
+         "/mirror/feed42.js" => Response::ok(pages().ondemand_js()),
+         _ => Response::status(404),
+      }
+   })
+}
+
+fn options(server: &StubServer) -> FetchOptions {
+   FetchOptions {
+      home_url: server.url("/home"),
+      // Another host name for the same server, like X's asset host.
+      ondemand_url_template: server
+         .url("/mirror/{hash}.js")
+         .replace("127.0.0.1", "localhost"),
+      retry: RetryPolicy::none(),
+      ..FetchOptions::default()
+   }
+}
+
+#[test]
+fn fetch_from_mirror() {
+   let server = mirror();
+   let fetched = ClientTransaction::fetch_with(&options(&server)).unwrap();
+   let expected =
+      ClientTransaction::new(&pages().home_page_html(), &pages().ondemand_js()).unwrap();
+
+   assert_eq!(fetched.key_bytes(), expected.key_bytes());
+   assert_eq!(fetched.animation_key(), expected.animation_key());
+   assert_eq!(
+      fetched.generate_transaction_id_at("GET", "/i/api/1.1/foo.json", 42),
+      expected.generate_transaction_id_at("GET", "/i/api/1.1/foo.json", 42)
+   );
+
+   let paths = server
+      .requests()
+      .into_iter()
+      .map(|request| request.path)
+      .collect::<Vec<_>>();
+   assert_eq!(paths, ["/home", "/mirror/feed42.js"]);
+}
+
+#[test]
+fn fetch_sends_configured_headers() {
+   let server = mirror();
+   let options = FetchOptions {
+      user_agent: "txid-test/1.0".into(),
+      headers: vec![("Accept-Language".into(), "en-US".into())],
+      cookies: Some("auth_token=abc; ct0=def".into()),
+      ..options(&server)
+   };
+   ClientTransaction::fetch_with(&options).unwrap();
+
+   for request in server.requests() {
+      assert_eq!(request.method, "GET");
+      assert_eq!(request.header("User-Agent"), Some("txid-test/1.0"));
+      assert_eq!(request.header("Accept-Language"), Some("en-US"));
+      let expected = (request.path == "/home").then_some("auth_token=abc; ct0=def");
+      assert_eq!(request.header("Cookie"), expected, "{}", request.path);
+   }
+}
+
+#[test]
+fn fetch_missing_ondemand_chunk() {
+   let server = mirror();
+   let options = FetchOptions {
+      ondemand_url_template: server.url("/elsewhere/{hash}.js"),
+      ..options(&server)
+   };
+
+   match ClientTransaction::fetch_with(&options) {
+      Err(Error::HttpStatus(404, "ondemand.js")) => {},
+      Err(err) => panic!("unexpected error: {err}"),
+      Ok(_) => panic!("fetch succeeded"),
+   }
+}
+
+#[test]
+fn fetch_falls_back_to_next_candidate() {
+   let server = mirror();
+   let options = FetchOptions {
+      ondemand_url_template: server.url("/mirror/{hash}a.js"),
+      ..options(&server)
+   };
+
+   let client = ClientTransaction::fetch_with(&options).unwrap();
+   assert_eq!(client.key_bytes(), pages().key_bytes());
+   // The alternate suffix has no hash to reuse the indices by.
+   assert_eq!(client.ondemand_hash(), None);
+   assert_eq!(server.hits("/mirror/feed42a.js"), 1);
+   assert_eq!(server.hits("/mirror/feed42.js"), 1);
+}
+
+#[test]
+fn fetch_enforces_max_body_size() {
+   let server = mirror();
+   let options = FetchOptions {
+      max_body_size: Some(1024),
+      ..options(&server)
+   };
+
+   match ClientTransaction::fetch_with(&options) {
+      Err(Error::BodyTooLarge(1024, "x.com")) => {},
+      Err(err) => panic!("unexpected error: {err}"),
+      Ok(_) => panic!("fetch succeeded"),
+   }
+}
+
+#[test]
+fn refresh_skips_unchanged_ondemand_chunk() {
+   let server = mirror();
+   let options = options(&server);
+
+   let client = ClientTransaction::fetch_with(&options).unwrap();
+   let refreshed = client.refresh_with(&options).unwrap();
+
+   assert_eq!(refreshed.indices(), client.indices());
+   assert_eq!(server.hits("/home"), 2);
+   assert_eq!(server.hits("/mirror/feed42.js"), 1);
+}
+
+#[test]
+fn fetch_syncs_clock_with_date_header() {
+   let server = StubServer::start(|request, hits| {
+      match request.path.as_str() {
+         // X's clock reads 2024-01-01 on the first visit only.
+         "/home" if hits == 0 => {
+            Response::ok(pages().home_page_html()).header("Date", "Mon, 01 Jan 2024 00:00:00 GMT")
+         },
+         "/home" => Response::ok(pages().home_page_html()),
+         "/mirror/feed42.js" => Response::ok(pages().ondemand_js()),
+         _ => Response::status(404),
+      }
+   });
+   let options = options(&server);
+
+   // 2024-01-01 is 21_142_800 seconds after X's epoch.
+   let assert_synced = |client: &ClientTransaction| {
+      let id = client.generate_transaction_id("GET", "/i/api/1.1/foo.json");
+      let time = DecodedTransactionId::decode(&id).unwrap().time;
+      assert!((21_142_800..21_142_805).contains(&time), "{time}");
+   };
+
+   let client = ClientTransaction::fetch_with(&options).unwrap();
+   assert!(client.clock_offset() < 0);
+   assert_synced(&client);
+
+   let refreshed = client.refresh_with(&options).unwrap();
+   assert_eq!(refreshed.clock_offset(), client.clock_offset());
+   assert_synced(&refreshed);
+}
+
+#[test]
+fn fetch_web_client_context() {
+   let server = StubServer::start(|request, _| {
+      match request.path.as_str() {
+         "/home" => {
+            let main_url = format!(
+               "http://{}/main.0a1b2c3a.js",
+               request.header("Host").unwrap()
+            );
+            Response::ok(pages().with_main_url(main_url).home_page_html())
+         },
+         "/mirror/feed42.js" => Response::ok(pages().ondemand_js()),
+         "/main.0a1b2c3a.js" => Response::ok(main_js()),
+         _ => Response::status(404),
+      }
+   });
+
+   let context = WebClientContext::fetch_with(&options(&server)).unwrap();
+   assert_eq!(context.bearer_token, BEARER_TOKEN);
+   assert_eq!(context.transaction.key_bytes(), pages().key_bytes());
+   assert!(context.graphql.get("UserByScreenName").is_some());
+   assert_eq!(server.hits("/main.0a1b2c3a.js"), 1);
+}
+
+#[test]
+fn session_fetches_with_its_cookies() {
+   let server = mirror();
+   let mut session = Session::from_cookie_header("auth_token=abc; ct0=def");
+   let options = FetchOptions {
+      cookies: Some("ct0=other".into()),
+      ..options(&server)
+   };
+
+   session.refresh_transaction(&options).unwrap();
+   session.refresh_transaction(&options).unwrap();
+
+   assert_eq!(
+      session.transaction().unwrap().key_bytes(),
+      pages().key_bytes()
+   );
+   assert_eq!(server.hits("/home"), 2);
+   assert_eq!(server.hits("/mirror/feed42.js"), 1);
+   for request in server.requests() {
+      let expected = (request.path == "/home").then_some("auth_token=abc; ct0=def");
+      assert_eq!(request.header("Cookie"), expected, "{}", request.path);
+   }
+}
+
+#[test]
+fn pool_fetches_with_account_cookies() {
+   let server = mirror();
+   let pool = ClientPool::fetching(options(&server), |account| {
+      (account != "anonymous").then(|| format!("auth_token={account}"))
+   });
+
+   pool.get("alice").unwrap();
+   pool.get("alice").unwrap();
+   pool.get("anonymous").unwrap();
+
+   let cookies = server
+      .requests()
+      .iter()
+      .filter(|request| request.path == "/home")
+      .map(|request| request.header("Cookie").map(ToOwned::to_owned))
+      .collect::<Vec<_>>();
+   assert_eq!(cookies, [Some("auth_token=alice".to_owned()), None]);
+   assert_eq!(pool.stats().len(), 2);
+}
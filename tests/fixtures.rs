@@ -0,0 +1,172 @@
+//! Regression tests against the golden fixtures in `tests/fixtures`.
+//!
+//! Each fixture directory holds a `home.html`, an `ondemand.js` and an
+//! `expected.txt` written by the `record_fixture` example. Every fixture must
+//! keep producing exactly the recorded values, so page formats that X has
+//! shipped before stay supported.
+
+#![expect(
+   clippy::tests_outside_test_module,
+   reason = "integration tests are a crate of their own"
+)]
+
+use std::{
+   fs,
+   path::{
+      Path,
+      PathBuf,
+   },
+};
+
+use data_encoding::BASE64;
+use xitter_txid::{
+   ClientTransaction,
+   canary::Fingerprint,
+   parse,
+};
+
+struct Fixture {
+   name:     String,
+   html:     String,
+   js:       String,
+   expected: Vec<(String, String)>,
+}
+
+impl Fixture {
+   fn load(dir: &Path) -> Self {
+      let read = |file: &str| {
+         fs::read_to_string(dir.join(file))
+            .unwrap_or_else(|err| panic!("{}: {file}: {err}", dir.display()))
+      };
+
+      let expected = read("expected.txt")
+         .lines()
+         .map(str::trim)
+         .filter(|line| !line.is_empty() && !line.starts_with('#'))
+         .map(|line| {
+            let (key, value) = line
+               .split_once(" = ")
+               .unwrap_or_else(|| panic!("{}: malformed line: {line}", dir.display()));
+            (key.to_owned(), value.to_owned())
+         })
+         .collect();
+
+      Self {
+         name: dir.file_name().unwrap().to_string_lossy().into_owned(),
+         html: read("home.html"),
+         js: read("ondemand.js"),
+         expected,
+      }
+   }
+
+   fn value<'a>(&'a self, key: &'a str) -> &'a str {
+      self
+         .values(key)
+         .next()
+         .unwrap_or_else(|| panic!("{}: missing `{key}`", self.name))
+   }
+
+   fn values<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+      self
+         .expected
+         .iter()
+         .filter(move |&&(ref name, _)| name == key)
+         .map(|&(_, ref value)| value.as_str())
+   }
+}
+
+fn fixtures() -> Vec<Fixture> {
+   let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+   let mut dirs = fs::read_dir(&root)
+      .unwrap()
+      .map(|entry| entry.unwrap().path())
+      .filter(|path| path.is_dir())
+      .collect::<Vec<_>>();
+   dirs.sort();
+
+   assert!(!dirs.is_empty(), "no fixtures in {}", root.display());
+   dirs.iter().map(|dir| Fixture::load(dir)).collect()
+}
+
+#[test]
+fn ondemand_url() {
+   for fixture in fixtures() {
+      let url = ClientTransaction::extract_ondemand_url(&fixture.html).unwrap();
+      assert_eq!(url, fixture.value("ondemand_url"), "{}", fixture.name);
+   }
+}
+
+#[test]
+fn canary_fingerprint() {
+   for fixture in fixtures() {
+      let fingerprint = Fingerprint::new(&fixture.html, &fixture.js);
+      let rows = parse::animation_frames(&fixture.html)
+         .iter()
+         .map(Vec::len)
+         .sum::<usize>();
+
+      assert_eq!(fingerprint.key_length, 48, "{}", fixture.name);
+      assert_eq!(fingerprint.frames, 4, "{}", fixture.name);
+      assert_eq!(fingerprint.row_lengths.len(), rows, "{}", fixture.name);
+      assert_eq!(fingerprint.index_patterns, 4, "{}", fixture.name);
+      assert!(fingerprint.generates, "{}", fixture.name);
+
+      let known_good = fingerprint.to_string().parse::<Fingerprint>().unwrap();
+      assert!(fingerprint.diff(&known_good).is_empty(), "{}", fixture.name);
+   }
+}
+
+#[test]
+fn key_material() {
+   for fixture in fixtures() {
+      let client = ClientTransaction::new(&fixture.html, &fixture.js).unwrap();
+
+      assert_eq!(
+         BASE64.encode(client.key_bytes()),
+         fixture.value("verification_key"),
+         "{}",
+         fixture.name
+      );
+      assert_eq!(
+         client.row_index().to_string(),
+         fixture.value("row_index"),
+         "{}",
+         fixture.name
+      );
+
+      let indices = fixture
+         .value("key_byte_indices")
+         .split_whitespace()
+         .map(|index| index.parse::<usize>().unwrap())
+         .collect::<Vec<_>>();
+      assert_eq!(client.key_byte_indices(), indices, "{}", fixture.name);
+
+      assert_eq!(
+         client.animation_key(),
+         fixture.value("animation_key"),
+         "{}",
+         fixture.name
+      );
+   }
+}
+
+#[test]
+fn transaction_ids() {
+   for fixture in fixtures() {
+      let client = ClientTransaction::new(&fixture.html, &fixture.js).unwrap();
+      let mut count = 0;
+
+      for line in fixture.values("transaction_id") {
+         let [time, method, path, expected] = line.split_whitespace().collect::<Vec<_>>()[..]
+         else {
+            panic!("{}: malformed transaction_id: {line}", fixture.name);
+         };
+
+         let id = client.generate_transaction_id_at(method, path, time.parse().unwrap());
+         assert_eq!(id, expected, "{}: {method} {path} at {time}", fixture.name);
+         count += 1;
+      }
+
+      assert!(count > 0, "{}: no transaction IDs recorded", fixture.name);
+   }
+}
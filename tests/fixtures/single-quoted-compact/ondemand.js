@@ -0,0 +1 @@
+"use strict";(self.webpackChunk_twitter_responsive_web=self.webpackChunk_twitter_responsive_web||[]).push([["ondemand.s"],{84129:(e,t,n)=>{n.d(t,{default:()=>s});var r=n(72379);function s(){const e=document.querySelector("[name^=tw]"),o=r.Z.decode(e.getAttribute("content"));return[parseInt(o[7],16),parseInt(o[23],16),parseInt(o[41],16),parseInt(o[47],16)].map(e=>e%16)}}}]);
@@ -0,0 +1,115 @@
+//! Guest token activation against a local stub of X's API.
+
+#![cfg(all(feature = "guest", feature = "fetch"))]
+#![expect(
+   clippy::tests_outside_test_module,
+   reason = "integration tests are a crate of their own"
+)]
+
+mod common;
+
+use std::time::Duration;
+
+use common::{
+   Response,
+   StubServer,
+};
+use xitter_txid::{
+   ClientTransaction,
+   DecodedTransactionId,
+   Error,
+   FetchOptions,
+   RetryPolicy,
+   guest::TokenCache,
+   testing::SyntheticPages,
+};
+
+const PATH: &str = "/1.1/guest/activate.json";
+
+fn client() -> ClientTransaction {
+   let pages = SyntheticPages::new((0..48).collect::<Vec<u8>>());
+   ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap()
+}
+
+fn options() -> FetchOptions {
+   FetchOptions {
+      retry: RetryPolicy::none(),
+      ..FetchOptions::default()
+   }
+}
+
+/// Hands out `1000`, `1001`, ... to authorized `POST`s.
+fn activation_server() -> StubServer {
+   StubServer::start(|request, count| {
+      if request.method != "POST" || request.path != PATH {
+         return Response::status(404);
+      }
+      if request.header("authorization") != Some("Bearer test-bearer") {
+         return Response::status(403);
+      }
+      Response::ok(format!(r#"{{"guest_token":"{}"}}"#, 1000 + count))
+   })
+}
+
+#[test]
+fn activates_and_caches() {
+   let server = activation_server();
+   let guest = TokenCache::new("test-bearer").with_activate_url(server.url(PATH));
+   let client = client();
+
+   let token = guest.token(&client, &options()).unwrap();
+   assert_eq!(token.token(), "1000");
+   assert!(!token.is_expired());
+   assert_eq!(guest.token(&client, &options()).unwrap().token(), "1000");
+   assert_eq!(server.hits(PATH), 1);
+
+   let refreshed = guest.refresh(&client, &options()).unwrap();
+   assert_eq!(refreshed.token(), "1001");
+   assert_eq!(guest.cached().unwrap().token(), "1001");
+}
+
+#[test]
+fn sends_transaction_id() {
+   let server = activation_server();
+   let guest = TokenCache::new("test-bearer").with_activate_url(server.url(PATH));
+   let client = client();
+   guest.token(&client, &options()).unwrap();
+
+   let request = &server.requests()[0];
+   let id = request.header("x-client-transaction-id").unwrap();
+   let decoded = DecodedTransactionId::decode(id).unwrap();
+   assert_eq!(decoded.key_bytes, client.key_bytes());
+}
+
+#[test]
+fn activation_is_not_retried() {
+   let server = StubServer::start(|_, _| Response::status(503));
+   let guest = TokenCache::new("test-bearer").with_activate_url(server.url(PATH));
+   let options = FetchOptions {
+      retry: RetryPolicy {
+         initial_backoff: Duration::ZERO,
+         ..RetryPolicy::default()
+      },
+      ..FetchOptions::default()
+   };
+
+   match guest.token(&client(), &options) {
+      Err(Error::HttpStatus(503, _)) => {},
+      Err(err) => panic!("unexpected error: {err}"),
+      Ok(token) => panic!("activated {token:?}"),
+   }
+   assert_eq!(server.hits(PATH), 1);
+}
+
+#[test]
+fn rejected_activation() {
+   let server = activation_server();
+   let guest = TokenCache::new("wrong-bearer").with_activate_url(server.url(PATH));
+
+   match guest.token(&client(), &options()) {
+      Err(Error::HttpStatus(403, _)) => {},
+      Err(err) => panic!("unexpected error: {err}"),
+      Ok(token) => panic!("activated {token:?}"),
+   }
+   assert!(guest.cached().is_none());
+}
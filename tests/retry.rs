@@ -0,0 +1,115 @@
+//! Retry behavior of `fetch_with` against a local stub server.
+
+#![cfg(feature = "fetch")]
+#![expect(
+   clippy::tests_outside_test_module,
+   reason = "integration tests are a crate of their own"
+)]
+
+mod common;
+
+use std::time::Duration;
+
+use common::{
+   Response,
+   StubServer,
+};
+use xitter_txid::{
+   ClientTransaction,
+   Error,
+   FetchOptions,
+   RetryPolicy,
+   testing::SyntheticPages,
+};
+
+const HASH: &str = "abc123";
+
+fn pages() -> SyntheticPages {
+   SyntheticPages::new((0..48).collect::<Vec<u8>>()).with_ondemand_hash(HASH)
+}
+
+fn options(server: &StubServer, max_attempts: u32) -> FetchOptions {
+   FetchOptions {
+      home_url: server.url("/"),
+      ondemand_url_template: server.url("/assets/ondemand.s.{hash}a.js"),
+      retry: RetryPolicy {
+         max_attempts,
+         initial_backoff: Duration::from_millis(1),
+         ..RetryPolicy::default()
+      },
+      ..FetchOptions::default()
+   }
+}
+
+fn fetch_err(options: &FetchOptions) -> Error {
+   match ClientTransaction::fetch_with(options) {
+      Ok(_) => panic!("fetch succeeded"),
+      Err(err) => err,
+   }
+}
+
+/// Serves the homepage after `failures` responses of `status`.
+fn flaky_server(failures: usize, failure: Response) -> StubServer {
+   StubServer::start(move |request, count| {
+      match request.path.as_str() {
+         "/" if count < failures => failure.clone(),
+         "/" => Response::ok(pages().home_page_html()),
+         path if path.ends_with(&format!("ondemand.s.{HASH}a.js")) => {
+            Response::ok(pages().ondemand_js())
+         },
+         _ => Response::status(404),
+      }
+   })
+}
+
+#[test]
+fn retries_rate_limit_then_succeeds() {
+   let server = flaky_server(2, Response::status(429).header("Retry-After", "0"));
+   let client = ClientTransaction::fetch_with(&options(&server, 3)).unwrap();
+
+   assert_eq!(client.key_bytes(), pages().key_bytes());
+   assert_eq!(server.hits("/"), 3);
+}
+
+#[test]
+fn retries_server_error_with_backoff() {
+   let server = flaky_server(1, Response::status(503));
+   ClientTransaction::fetch_with(&options(&server, 3)).unwrap();
+   assert_eq!(server.hits("/"), 2);
+}
+
+#[test]
+fn gives_up_after_max_attempts() {
+   let server = flaky_server(usize::MAX, Response::status(500));
+   let err = fetch_err(&options(&server, 2));
+
+   assert!(matches!(err, Error::HttpStatus(500, _)), "{err}");
+   assert_eq!(server.hits("/"), 2);
+}
+
+#[test]
+fn client_errors_are_fatal() {
+   let server = flaky_server(usize::MAX, Response::status(404));
+   let err = fetch_err(&options(&server, 3));
+
+   assert!(matches!(err, Error::HttpStatus(404, _)), "{err}");
+   assert_eq!(server.hits("/"), 1);
+}
+
+#[test]
+fn parse_errors_are_fatal() {
+   let server = flaky_server(usize::MAX, Response::ok("<html>nothing here</html>"));
+   let err = fetch_err(&options(&server, 3));
+
+   assert!(matches!(err, Error::MissingKey(_)), "{err}");
+   assert_eq!(server.hits("/"), 1);
+}
+
+#[test]
+fn long_retry_after_is_not_waited_out() {
+   let server = flaky_server(1, Response::status(429).header("Retry-After", "3600"));
+   let err = fetch_err(&options(&server, 3));
+
+   assert!(matches!(err, Error::HttpStatus(429, _)), "{err}");
+   assert_eq!(server.hits("/"), 1);
+}
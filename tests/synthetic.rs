@@ -0,0 +1,70 @@
+//! End-to-end tests of `ClientTransaction::new` on synthetic pages.
+
+#![expect(
+   clippy::tests_outside_test_module,
+   reason = "integration tests are a crate of their own"
+)]
+
+use xitter_txid::{
+   ClientTransaction,
+   testing::{
+      SyntheticPages,
+      default_frames,
+   },
+};
+
+fn key() -> Vec<u8> {
+   (0..48)
+      .map(|byte: u8| byte.wrapping_mul(37).wrapping_add(11))
+      .collect()
+}
+
+#[test]
+fn client_from_synthetic_pages() {
+   let pages = SyntheticPages::new(key()).with_indices(4, vec![20, 31, 9]);
+   let client = ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap();
+
+   assert_eq!(client.key_bytes(), key());
+   assert_eq!(client.row_index(), 4);
+   assert_eq!(client.key_byte_indices(), [20, 31, 9]);
+   assert!(!client.animation_key().is_empty());
+}
+
+#[test]
+fn ondemand_url_from_synthetic_pages() {
+   let pages = SyntheticPages::new(key()).with_ondemand_hash("d00dfeed");
+   let url = ClientTransaction::extract_ondemand_url(&pages.home_page_html()).unwrap();
+   assert!(url.ends_with("/ondemand.s.d00dfeeda.js"));
+}
+
+#[test]
+fn animation_key_follows_selected_frame() {
+   let mut frames = default_frames();
+   let pages = SyntheticPages::new(key()).with_frames(frames.clone());
+   let original = ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap();
+
+   // Key byte 5 selects the frame, so changing any other frame has no effect.
+   let selected = usize::from(key()[5] % 4);
+   let other = (selected + 1) % 4;
+   frames[other] = vec![vec![0; 11]; 16];
+   let pages = pages.with_frames(frames.clone());
+   let unchanged = ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap();
+   assert_eq!(original.animation_key(), unchanged.animation_key());
+
+   frames[selected] = vec![vec![255; 11]; 16];
+   let pages = pages.with_frames(frames);
+   let changed = ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap();
+   assert_ne!(original.animation_key(), changed.animation_key());
+}
+
+#[test]
+fn transaction_id_is_deterministic() {
+   let pages = SyntheticPages::new(key());
+   let client = ClientTransaction::new(&pages.home_page_html(), &pages.ondemand_js()).unwrap();
+
+   let first = client.generate_transaction_id_at("GET", "/i/api/1.1/foo.json", 1234);
+   let second = client.generate_transaction_id_at("GET", "/i/api/1.1/foo.json", 1234);
+   let other = client.generate_transaction_id_at("GET", "/i/api/1.1/foo.json", 1235);
+   assert_eq!(first, second);
+   assert_ne!(first, other);
+}